@@ -0,0 +1,38 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::jobs::JobState;
+
+pub struct BgCommand;
+
+impl Command for BgCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        if !registry.has_job_control() {
+            return Err("bg: no job control".to_string());
+        }
+
+        let specs = if args.is_empty() { vec!["%+".to_string()] } else { args.to_vec() };
+        let mut jobs = registry.jobs();
+
+        for spec in &specs {
+            let id = jobs.resolve(spec).map_err(|e| format!("bg: {}", e))?;
+            let Some(job) = jobs.get_mut(id) else { continue };
+
+            if job.state() != JobState::Stopped {
+                writeln!(error, "bg: job {} already in background", id).map_err(|e| e.to_string())?;
+                continue;
+            }
+            job.resume();
+            jobs.touch(id);
+
+            let command = jobs.get(id).map(|job| job.command.clone()).unwrap_or_default();
+            writeln!(output, "[{}]{} {} &", id, jobs.marker(id), command).map_err(|e| e.to_string())?;
+        }
+
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "bg"
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+pub struct BuiltinCommand;
+
+impl Command for BuiltinCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, input: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let args = args.strip_prefix(&["--".to_string()]).unwrap_or(args);
+        let Some((name, args)) = args.split_first() else {
+            return Ok(ShellStatus::Continue);
+        };
+
+        // Called directly, so a function of the same name can wrap it.
+        match registry.get_builtin(name) {
+            Some(builtin) => builtin.execute(args, registry, input, output, error),
+            None => {
+                writeln!(error, "builtin: {}: not a shell builtin", name).map_err(|e| e.to_string())?;
+                Ok(ShellStatus::Status(1))
+            }
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "builtin"
+    }
+}
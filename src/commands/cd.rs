@@ -1,29 +1,30 @@
 use super::{Command, ShellStatus, CommandRegistry};
 use std::env;
-use std::io::Write;
-use std::path::Path;
+use std::io::{Read, Write};
 
 pub struct CdCommand;
 
 
 impl Command for CdCommand {
-    fn execute(&self, args: &[String], _: &CommandRegistry, _: &mut dyn Write) -> Result<ShellStatus, String> {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        if registry.is_option_set("restricted") {
+            return Err("cd: restricted".to_string());
+        }
         if args.is_empty() {
              return Ok(ShellStatus::Continue);
         }
 
         if args[0] == "~" {
-            let home_path: String = env::var("HOME").unwrap_or_default();
-            if let Err(_) = env::set_current_dir(&home_path) {
+            let home_path: String = registry.get_variable("HOME").unwrap_or_default();
+            if env::set_current_dir(&home_path).is_err() {
                 return Err(format!("cd: {}: No such file or directory", home_path));
             }
             return Ok(ShellStatus::Continue);
         }
 
         let new_dir = &args[0];
-        let root = Path::new(new_dir);
 
-        if let Err(_) = env::set_current_dir(&root) {
+        if env::set_current_dir(new_dir).is_err() {
             return Err(format!("cd: {}: No such file or directory", new_dir));
         }
 
@@ -0,0 +1,102 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::command_cache::is_executable;
+use super::type_cmd::describe;
+use crate::utils::KEYWORDS;
+
+/// The `PATH` searched by `command -p`, where the standard utilities live
+/// whatever the user's own `PATH` says.
+const DEFAULT_PATH: &str = "/bin:/usr/bin";
+
+pub struct CommandCommand;
+
+impl Command for CommandCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, input: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut default_path = false;
+        let mut brief = false;
+        let mut verbose = false;
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    'p' => default_path = true,
+                    'v' => brief = true,
+                    'V' => verbose = true,
+                    _ => {
+                        writeln!(error, "command: -{}: invalid option", flag).map_err(|e| e.to_string())?;
+                        writeln!(error, "command: usage: command [-pVv] command [arg ...]").map_err(|e| e.to_string())?;
+                        return Ok(ShellStatus::Status(2));
+                    }
+                }
+            }
+        }
+        if default_path && registry.is_option_set("restricted") {
+            writeln!(error, "command: -p: restricted").map_err(|e| e.to_string())?;
+            return Ok(ShellStatus::Status(1));
+        }
+        let rest: Vec<&String> = iter.collect();
+        let find = |name: &str| if default_path { find_in_default_path(name) } else { registry.get_executable_path(name) };
+
+        // Succeeds if any of the names is found, or there are none.
+        if brief || verbose {
+            let mut status = if rest.is_empty() { 0 } else { 1 };
+            for name in rest {
+                let found = if verbose {
+                    describe(name, registry, find(name), output)?
+                } else if KEYWORDS.contains(&name.as_str()) || registry.get_function(name).is_some() || registry.get_builtin(name).is_some() {
+                    writeln!(output, "{}", name).map_err(|e| e.to_string())?;
+                    true
+                } else if let Some(path) = find(name) {
+                    writeln!(output, "{}", path).map_err(|e| e.to_string())?;
+                    true
+                } else {
+                    false
+                };
+                if found {
+                    status = 0;
+                } else if verbose {
+                    writeln!(error, "command: {}: not found", name).map_err(|e| e.to_string())?;
+                }
+            }
+            return Ok(ShellStatus::Status(status));
+        }
+
+        let Some((name, args)) = rest.split_first() else {
+            return Ok(ShellStatus::Continue);
+        };
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+        // Functions are skipped, which is what lets `cd() { command cd "$@"; }`
+        // reach the real `cd`.
+        if let Some(builtin) = registry.get_builtin(name) {
+            return builtin.execute(&args, registry, input, output, error);
+        }
+
+        // The executor runs the external commands given to `command` (and
+        // `builtin command`) as pipeline stages of their own, with their
+        // redirections, so anything left here isn't a command at all. A
+        // restricted shell still refuses a path outright.
+        if name.contains('/') && registry.is_option_set("restricted") {
+            writeln!(error, "{}: restricted: cannot specify `/' in command names", name).map_err(|e| e.to_string())?;
+            return Ok(ShellStatus::Status(1));
+        }
+        writeln!(error, "{}: command not found", name).map_err(|e| e.to_string())?;
+        Ok(ShellStatus::Status(127))
+    }
+
+    fn get_name(&self) -> &str {
+        "command"
+    }
+}
+
+/// Looks `name` up in `DEFAULT_PATH`, for `command -p`.
+pub(super) fn find_in_default_path(name: &str) -> Option<String> {
+    DEFAULT_PATH.split(':')
+        .map(|directory| format!("{}/{}", directory, name))
+        .find(|path| is_executable(path))
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+struct CachedCommand {
+    path: String,
+    /// `None` for commands only seen while listing `PATH`; these are not
+    /// shown by `hash` until they're used.
+    hits: Option<u32>,
+}
+
+/// Executables found in `PATH`, remembered so that running a command
+/// doesn't search every directory again. Also backs completion, which needs
+/// every name in `PATH`.
+#[derive(Default)]
+pub struct CommandCache {
+    commands: BTreeMap<String, CachedCommand>,
+    scanned: bool,
+}
+
+impl CommandCache {
+    /// The cached path for a command, forgetting it if the file has gone.
+    pub fn get(&mut self, name: &str) -> Option<String> {
+        let path = &self.commands.get(name)?.path;
+        if !is_executable(path) {
+            self.commands.remove(name);
+            return None;
+        }
+        Some(path.clone())
+    }
+
+    /// Remembers a path found by a `PATH` search, without hashing it.
+    pub fn remember(&mut self, name: &str, path: &str) {
+        self.commands.entry(name.to_string()).or_insert_with(|| CachedCommand { path: path.to_string(), hits: None });
+    }
+
+    /// Adds a command to the hash table, as `hash name` and `hash -p` do.
+    /// Hits are kept when the path is unchanged.
+    pub fn insert(&mut self, name: &str, path: &str) {
+        let hits = self.commands.get(name)
+            .filter(|command| command.path == path)
+            .and_then(|command| command.hits);
+        self.commands.insert(name.to_string(), CachedCommand { path: path.to_string(), hits: Some(hits.unwrap_or(0)) });
+    }
+
+    pub fn record_hit(&mut self, name: &str) {
+        if let Some(command) = self.commands.get_mut(name) {
+            command.hits = Some(command.hits.unwrap_or(0) + 1);
+        }
+    }
+
+    /// Hashed commands as `(name, path, hits)`, sorted by name.
+    pub fn hashed(&self) -> Vec<(&str, &str, u32)> {
+        self.commands.iter()
+            .filter_map(|(name, command)| Some((name.as_str(), command.path.as_str(), command.hits?)))
+            .collect()
+    }
+
+    /// Forgets everything, for `hash -r` and when `PATH` changes.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.scanned = false;
+    }
+
+    /// Every executable name in `PATH`, listing the directories the first
+    /// time it's needed after the cache was cleared.
+    pub fn names(&mut self, path_var: &str) -> Vec<String> {
+        if !self.scanned {
+            self.scan(path_var);
+        }
+        self.commands.keys().cloned().collect()
+    }
+
+    fn scan(&mut self, path_var: &str) {
+        for dir in path_var.split(':') {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().into_string().unwrap_or_default();
+                let path = format!("{}/{}", dir, name);
+                if !self.commands.contains_key(&name) && is_executable(&path) {
+                    self.remember(&name, &path);
+                }
+            }
+        }
+        self.scanned = true;
+    }
+}
+
+pub fn is_executable(path: &str) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
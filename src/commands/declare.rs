@@ -0,0 +1,140 @@
+use std::io::{Read, Write};
+use std::iter::Peekable;
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::variables::Value;
+use crate::utils::{is_valid_name, quote};
+
+pub struct DeclareCommand;
+
+impl Command for DeclareCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut indexed = false;
+        let mut associative = false;
+        let mut print = false;
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    'a' => indexed = true,
+                    'A' => associative = true,
+                    'p' => print = true,
+                    _ => return Err(format!("declare: -{}: invalid option", flag)),
+                }
+            }
+        }
+
+        let operands = collect_operands(iter);
+
+        if operands.is_empty() {
+            for name in registry.get_variable_names() {
+                print_declaration(&name, registry, output)?;
+            }
+            return Ok(ShellStatus::Continue);
+        }
+
+        for operand in operands {
+            if print {
+                if !print_declaration(&operand.name, registry, output)? {
+                    return Err(format!("declare: {}: not found", operand.name));
+                }
+                continue;
+            }
+
+            if !is_valid_name(&operand.name) {
+                return Err(format!("declare: `{}': not a valid identifier", operand.name));
+            }
+            if associative || indexed {
+                registry.declare_array(&operand.name, associative)?;
+            }
+
+            match operand.value {
+                Some(OperandValue::Scalar(value)) => registry.set_variable(&operand.name, &value)?,
+                Some(OperandValue::Array(elements)) => registry.set_array(&operand.name, elements)?,
+                None => {}
+            }
+        }
+
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "declare"
+    }
+}
+
+enum OperandValue {
+    Scalar(String),
+    Array(Vec<(Option<String>, String)>),
+}
+
+struct Operand {
+    name: String,
+    value: Option<OperandValue>,
+}
+
+/// Rebuilds `name=value` and `name=(a [k]=v ...)` operands. The parser hands
+/// an array literal to builtins as separate `name=`, `(`, ..., `)` words.
+fn collect_operands<'a>(mut args: Peekable<impl Iterator<Item = &'a String>>) -> Vec<Operand> {
+    let mut operands = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let Some((name, value)) = arg.split_once('=') else {
+            operands.push(Operand { name: arg.clone(), value: None });
+            continue;
+        };
+
+        let value = if value.is_empty() && args.next_if(|next| next.as_str() == "(").is_some() {
+            let elements = args.by_ref()
+                .take_while(|element| element.as_str() != ")")
+                .map(|element| split_keyed_element(element))
+                .collect();
+            OperandValue::Array(elements)
+        } else {
+            OperandValue::Scalar(value.to_string())
+        };
+
+        operands.push(Operand { name: name.to_string(), value: Some(value) });
+    }
+
+    operands
+}
+
+fn split_keyed_element(element: &str) -> (Option<String>, String) {
+    let keyed = element.strip_prefix('[').and_then(|rest| rest.split_once("]="));
+    match keyed {
+        Some((key, value)) => (Some(key.to_string()), value.to_string()),
+        None => (None, element.to_string()),
+    }
+}
+
+/// Prints a variable the way `declare -p` does, so the output can be read
+/// back in. Returns `false` when the variable does not exist.
+fn print_declaration(name: &str, registry: &CommandRegistry, output: &mut dyn Write) -> Result<bool, String> {
+    let Some(value) = registry.get_value(name) else {
+        return Ok(false);
+    };
+
+    let line = match value {
+        Value::Scalar(value) => {
+            let flag = if registry.is_exported(name) { "-x" } else { "--" };
+            format!("declare {} {}={}", flag, name, quote(&value))
+        }
+        Value::Indexed(elements) => {
+            let elements: Vec<String> = elements.iter()
+                .map(|(index, value)| format!("[{}]={}", index, quote(value)))
+                .collect();
+            format!("declare -a {}=({})", name, elements.join(" "))
+        }
+        Value::Associative(elements) => {
+            let elements: Vec<String> = elements.iter()
+                .map(|(key, value)| format!("[{}]={}", key, quote(value)))
+                .collect();
+            format!("declare -A {}=({})", name, elements.join(" "))
+        }
+    };
+
+    writeln!(output, "{}", line).map_err(|e| e.to_string())?;
+    Ok(true)
+}
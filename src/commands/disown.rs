@@ -0,0 +1,28 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+pub struct DisownCommand;
+
+impl Command for DisownCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut jobs = registry.jobs();
+
+        let ids = match args.first().map(String::as_str) {
+            Some("-a") => jobs.ids(),
+            Some(_) => args.iter().map(|spec| jobs.resolve(spec).map_err(|e| format!("disown: {}", e)))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![jobs.resolve("%+").map_err(|e| format!("disown: {}", e))?],
+        };
+
+        for id in ids {
+            jobs.remove(id);
+        }
+
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "disown"
+    }
+}
@@ -1,11 +1,11 @@
-use std::io::Write;
+use std::io::{Read, Write};
 
 use super::{{Command, ShellStatus, CommandRegistry}};
 
 pub struct EchoCommand;
 
 impl Command for EchoCommand {
-    fn execute(&self, args: &[String], _: &CommandRegistry, output: &mut dyn Write) -> Result<ShellStatus, String> {
+    fn execute(&self, args: &[String], _: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
         writeln!(output, "{}", args.join(" ")).map_err(|e| e.to_string())?;
         Ok(ShellStatus::Continue)
     }
@@ -0,0 +1,66 @@
+use std::io::{self, Read, Write};
+use std::os::unix::process::CommandExt;
+
+use super::{Command, ShellStatus, CommandRegistry, ShellExecutor};
+use super::executor::process_command;
+use super::signals::restore_default_signals;
+
+pub struct ExecCommand;
+
+impl Command for ExecCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        // The executor has already applied the redirections to the shell, so
+        // without a command there is nothing left to do.
+        let Some((name, args)) = args.split_first() else {
+            return Ok(ShellStatus::Continue);
+        };
+        if registry.is_option_set("restricted") {
+            writeln!(error, "exec: restricted").map_err(|e| e.to_string())?;
+            return Ok(ShellStatus::Status(1));
+        }
+
+        let path = if name.contains('/') {
+            name.clone()
+        } else {
+            match registry.get_executable_path(name) {
+                Some(path) => path,
+                None => {
+                    writeln!(error, "exec: {}: not found", name).map_err(|e| e.to_string())?;
+                    return Ok(failed(registry, 127));
+                }
+            }
+        };
+
+        registry.save_history();
+        ShellExecutor::new(registry).run_exit_trap(registry.get_last_status());
+        let _ = io::stdout().flush();
+
+        let mut command = process_command(&path, name)?;
+        command.args(args).env_clear().envs(registry.get_exported_variables());
+        let ignored = registry.traps().ignored_signals();
+        unsafe {
+            command.pre_exec(move || {
+                restore_default_signals(&ignored);
+                Ok(())
+            });
+        }
+
+        // Only returns if the exec failed.
+        let e = command.exec();
+        writeln!(error, "exec: {}: {}", name, e).map_err(|e| e.to_string())?;
+        Ok(failed(registry, 126))
+    }
+
+    fn get_name(&self) -> &str {
+        "exec"
+    }
+}
+
+/// A failed `exec` ends a non-interactive shell, as in bash.
+fn failed(registry: &CommandRegistry, code: i32) -> ShellStatus {
+    if registry.is_interactive() {
+        ShellStatus::Status(code)
+    } else {
+        ShellStatus::Exit(code)
+    }
+}
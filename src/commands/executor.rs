@@ -1,174 +1,1111 @@
-use std::os::unix::process::CommandExt;
-use std::process::{Command as ProcessCommand, Stdio, Child};
-use std::io::{self, Write}; 
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command as ProcessCommand, Stdio, Child, ExitStatus};
+use std::io::{self, ErrorKind, Read, Write};
+use std::fs::{self, File};
+use std::env;
+use std::cell::Cell;
+use std::os::unix::fs::PermissionsExt;
+use std::time::Instant;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use super::{CommandRegistry, ShellStatus};
-use crate::utils::{ParsedCommand};
-use crate::utils::open_file;
+use super::jobs::{Job, JobState};
+use super::signals::{TrapEvent, exit_on_interrupt, restore_default_signals, restore_interrupt_signals, take_pending_signals, take_wait_interrupt};
+use super::expansion::{expand_word, expand_words, expand_to_string};
+use super::command::find_in_default_path;
+use super::times::{ChildTimes, CpuTimes, DEFAULT_TIMEFORMAT, POSIX_TIMEFORMAT, format_times};
+use crate::utils::{AndOr, Assignment, AssignmentValue, FdRedirect, ParsedCommand, Pipeline, TimeFormat, closest_matches, format_pipeline, logical_lines, parse_input, parse_prompt};
+use crate::utils::{RawStdin, open_file, open_input_file, quote};
 
 enum PipeState {
     None,
-    Process(Child),  
-    Buffer(Vec<u8>),  
+    Process(Child),
+    /// A builtin forked into a subshell so it streams into the next stage.
+    Subshell { pid: libc::pid_t, output: Option<File> },
+}
+
+impl PipeState {
+    fn pid(&self) -> Option<libc::pid_t> {
+        match self {
+            PipeState::Process(child) => Some(child.id() as libc::pid_t),
+            PipeState::Subshell { pid, .. } => Some(*pid),
+            PipeState::None => None,
+        }
+    }
+
+    /// Takes the read end of the pipe the stage writes into, if it has one.
+    fn take_output(&mut self) -> Option<OwnedFd> {
+        match self {
+            PipeState::Process(child) => child.stdout.take().map(OwnedFd::from),
+            PipeState::Subshell { output, .. } => output.take().map(OwnedFd::from),
+            PipeState::None => None,
+        }
+    }
+}
+
+/// Where an external stage's stdout goes.
+#[derive(Clone, Copy, PartialEq)]
+enum StageOutput {
+    /// Into a pipe to the next stage.
+    Pipe,
+    /// To the shell's own stdout.
+    Inherit,
+    /// To the shell's own stdout, with the command replacing the shell.
+    Exec,
 }
 
 pub struct ShellExecutor<'a> {
     registry: &'a CommandRegistry,
+    /// Set in the subshell of a lone background command, which then execs
+    /// the command instead of spawning it, so `$!` is the command itself.
+    /// Taken by the first pipeline run, so function and trap bodies never
+    /// see it.
+    exec_in_place: Cell<bool>,
 }
 
 impl<'a> ShellExecutor<'a> {
     pub fn new(registry: &'a CommandRegistry) -> Self {
-        Self { registry }
+        Self { registry, exec_in_place: Cell::new(false) }
+    }
+
+    /// Runs every line of `source` in the current shell, stopping at `exit`.
+    /// Errors are reported on stderr and the script carries on, like `sh`.
+    pub fn run_script(&self, source: &str) -> ShellStatus {
+        for (number, line) in logical_lines(source) {
+            self.registry.set_line_number(number);
+
+            let status = self.run_line(&line);
+            if status.stops_execution() {
+                return status;
+            }
+        }
+
+        ShellStatus::Continue
+    }
+
+    /// Runs each pipeline of a line in turn. Trap handlers get their chance
+    /// between pipelines: `DEBUG` before each one, `ERR` after a failure, and
+    /// the handlers of any signals that arrived meanwhile. A syntax error
+    /// skips the line with status 2, and ends a non-interactive shell.
+    pub fn run_line(&self, line: &str) -> ShellStatus {
+        // `set -v` echoes input as it's read.
+        if self.registry.is_option_set("verbose") {
+            eprintln!("{}", line);
+        }
+        match parse_input(line) {
+            Ok(pipelines) => self.run_pipelines(&pipelines),
+            Err(e) => {
+                self.report_error(&e);
+                self.registry.set_last_status(2);
+                if self.registry.is_interactive() {
+                    ShellStatus::Status(2)
+                } else {
+                    self.exit_shell(2)
+                }
+            }
+        }
+    }
+
+    fn run_pipelines(&self, pipelines: &[Pipeline]) -> ShellStatus {
+        let mut iter = pipelines.iter().peekable();
+        while let Some(pipeline) = iter.next() {
+            // `a && b` skips `b` unless `a` succeeded, `a || b` unless it failed.
+            let succeeded = self.registry.get_last_status() == 0;
+            match pipeline.and_or {
+                Some(AndOr::And) if !succeeded => continue,
+                Some(AndOr::Or) if succeeded => continue,
+                _ => {}
+            }
+
+            if let ShellStatus::Exit(code) = self.run_trap(TrapEvent::Debug) {
+                return ShellStatus::Exit(code);
+            }
+
+            if pipeline.background {
+                if let Err(e) = self.run_background(pipeline) {
+                    self.report_error(&e);
+                }
+            } else {
+                // A status that is tested, by `!` or by `&&` / `||` after it,
+                // is not a failure as far as `set -e` and the ERR trap go.
+                let tested = pipeline.negated || iter.peek().is_some_and(|next| next.and_or.is_some());
+                if tested {
+                    self.registry.enter_condition();
+                }
+                let result = self.run(pipeline);
+                if tested {
+                    self.registry.leave_condition();
+                }
+
+                match result {
+                    Ok(status) if status.stops_execution() => return status,
+                    Ok(_) => {}
+                    Err(e) => {
+                        self.report_error(&e);
+                        if self.registry.take_fatal_error() && !self.registry.is_interactive() {
+                            return self.exit_shell(1);
+                        }
+                    }
+                }
+
+                let status = self.registry.get_last_status();
+                if status != 0 && !tested && !self.registry.in_condition() {
+                    if let ShellStatus::Exit(code) = self.run_trap(TrapEvent::Err) {
+                        return ShellStatus::Exit(code);
+                    }
+                    if self.registry.is_option_set("errexit") {
+                        return self.exit_shell(status);
+                    }
+                }
+            }
+
+            if let ShellStatus::Exit(code) = self.run_pending_traps() {
+                return ShellStatus::Exit(code);
+            }
+        }
+
+        ShellStatus::Continue
+    }
+
+    /// Runs the handler set for `event`, if any. Handlers see the `$?` of
+    /// the command that triggered them, and leave it untouched unless they
+    /// `exit`.
+    pub fn run_trap(&self, event: TrapEvent) -> ShellStatus {
+        let action = {
+            let mut traps = self.registry.traps();
+            match traps.get(event) {
+                Some(action) if !action.is_empty() && !traps.is_running() => {
+                    let action = action.clone();
+                    traps.set_running(true);
+                    action
+                }
+                _ => return ShellStatus::Continue,
+            }
+        };
+
+        let status = self.registry.get_last_status();
+        let line = self.registry.set_line_number(0);
+        let result = self.run_script(&action);
+        self.registry.set_line_number(line);
+        self.registry.set_last_status(status);
+        self.registry.traps().set_running(false);
+
+        match result {
+            ShellStatus::Exit(code) => ShellStatus::Exit(code),
+            _ => ShellStatus::Continue,
+        }
+    }
+
+    /// Runs the handlers of trapped signals received since the last check.
+    pub fn run_pending_traps(&self) -> ShellStatus {
+        if self.registry.traps().is_running() {
+            return ShellStatus::Continue;
+        }
+
+        for signal in take_pending_signals() {
+            if let ShellStatus::Exit(code) = self.run_trap(TrapEvent::Signal(signal)) {
+                return ShellStatus::Exit(code);
+            }
+        }
+        ShellStatus::Continue
+    }
+
+    /// Runs the `EXIT` trap (once) as the shell is about to exit with `code`,
+    /// returning the status to exit with; `exit` inside the handler wins.
+    /// Leaves the shell as `exit` would, for `set -e` and fatal errors.
+    fn exit_shell(&self, code: i32) -> ShellStatus {
+        self.registry.save_history();
+        ShellStatus::Exit(self.run_exit_trap(code))
+    }
+
+    pub fn run_exit_trap(&self, code: i32) -> i32 {
+        let Some(action) = self.registry.traps().take(TrapEvent::Exit) else {
+            return code;
+        };
+
+        self.registry.set_last_status(code);
+        match self.run_script(&action) {
+            ShellStatus::Exit(code) => code,
+            _ => code,
+        }
+    }
+
+    /// Forks a subshell that runs `body` and exits with its status. Returns
+    /// the child's pid in the parent.
+    fn fork_subshell(&self, body: impl FnOnce() -> ShellStatus) -> Result<libc::pid_t, String> {
+        let _ = io::stdout().flush();
+
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            return Err(format!("fork: {}", io::Error::last_os_error()));
+        }
+
+        if pid == 0 {
+            self.registry.set_job_control(false);
+            restore_interrupt_signals();
+            self.registry.traps().reset_for_subshell();
+            let code = match body() {
+                ShellStatus::Exit(code) | ShellStatus::Return(code) | ShellStatus::Status(code) => code,
+                ShellStatus::Continue => self.registry.get_last_status(),
+            };
+            let _ = io::stdout().flush();
+            unsafe { libc::_exit(code) }
+        }
+
+        Ok(pid)
     }
 
-    pub fn run(&self, pipeline: &[ParsedCommand]) -> Result<ShellStatus, String> {
-        if pipeline.is_empty() {
-            return Ok(ShellStatus::Continue);
+    /// Runs `source` in a forked subshell and returns what it wrote to stdout
+    /// without trailing newlines, for `$(...)`. `$?` becomes its exit status.
+    pub fn capture_output(&self, source: &str) -> Result<String, String> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(format!("command substitution: {}", io::Error::last_os_error()));
         }
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        let pid = self.fork_subshell(|| {
+            unsafe {
+                libc::close(read_fd);
+                libc::dup2(write_fd, 1);
+                libc::close(write_fd);
+            }
+            self.run_script(source)
+        });
+
+        unsafe { libc::close(write_fd) };
+        let mut reader = unsafe { File::from_raw_fd(read_fd) };
+        let pid = pid?;
+
+        let mut output = Vec::new();
+        let read = reader.read_to_end(&mut output);
+
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        self.registry.set_last_status(status_code(ExitStatus::from_raw(status)));
+        read.map_err(|e| format!("command substitution: {}", e))?;
+
+        let output = String::from_utf8_lossy(&output);
+        Ok(output.trim_end_matches('\n').to_string())
+    }
+
+    /// Starts `pipeline &` in a subshell and records it in the job table
+    /// instead of waiting for it.
+    fn run_background(&self, pipeline: &Pipeline) -> Result<(), String> {
+        let pid = self.fork_subshell(|| {
+            unsafe { libc::setpgid(0, 0) };
+            let lone_command = pipeline.commands.len() == 1 && !pipeline.negated && pipeline.time.is_none();
+            let executor = ShellExecutor { registry: self.registry, exec_in_place: Cell::new(lone_command) };
+            match executor.run(pipeline) {
+                Ok(status) => status,
+                Err(e) => {
+                    self.report_error(&e);
+                    ShellStatus::Continue
+                }
+            }
+        })?;
+        unsafe { libc::setpgid(pid, pid) };
+
+        let id = self.registry.jobs().add(Job::new(pid, &[pid], format_pipeline(pipeline)));
+        self.registry.set_last_background_pid(pid as u32);
+        self.registry.set_last_status(0);
+
+        if self.registry.is_interactive() {
+            eprintln!("[{}] {}", id, pid);
+        }
+        Ok(())
+    }
+
+    /// Non-interactive shells prefix diagnostics with the script name and
+    /// line number so failures can be traced back to their source.
+    fn report_error(&self, message: &str) {
+        if self.registry.is_interactive() {
+            eprintln!("{}", message);
+        } else {
+            let name = self.registry.get_variable("0").unwrap_or_default();
+            let line = self.registry.get_variable("LINENO").unwrap_or_default();
+            eprintln!("{}: line {}: {}", name, line, message);
+        }
+    }
+
+    /// Runs a foreground pipeline, reporting how long it took when it's
+    /// prefixed by `time` and inverting its status when it's prefixed by `!`.
+    pub fn run(&self, pipeline: &Pipeline) -> Result<ShellStatus, String> {
+        let outcome = self.run_timed(pipeline);
+        if pipeline.negated {
+            let status = self.registry.get_last_status();
+            self.registry.set_last_status(if status == 0 { 1 } else { 0 });
+        }
+        outcome
+    }
+
+    fn run_timed(&self, pipeline: &Pipeline) -> Result<ShellStatus, String> {
+        let mut children = ChildTimes::default();
+        let Some(format) = pipeline.time else {
+            return self.run_stages(pipeline, &mut children);
+        };
+
+        // The shell's own time covers builtins run in place; the pipeline's
+        // processes are counted from what `wait4` reports as they're reaped.
+        let started = Instant::now();
+        let before = CpuTimes::now();
+        let outcome = if pipeline.commands.is_empty() {
+            self.registry.set_last_status(0);
+            Ok(ShellStatus::Continue)
+        } else {
+            self.run_stages(pipeline, &mut children)
+        };
+        let (shell_user, shell_system) = CpuTimes::now().shell_since(&before);
+        let (user, system) = (shell_user + children.user, shell_system + children.system);
+
+        let format = match format {
+            TimeFormat::Posix => POSIX_TIMEFORMAT.to_string(),
+            TimeFormat::Variable => self.registry.get_variable("TIMEFORMAT").unwrap_or_else(|| DEFAULT_TIMEFORMAT.to_string()),
+        };
+        match format_times(&format, started.elapsed(), user, system) {
+            Ok(report) if report.is_empty() => {}
+            Ok(report) => eprintln!("{}", report),
+            Err(e) => self.report_error(&e),
+        }
+        outcome
+    }
+
+    /// Runs the stages of a foreground pipeline. With job control the
+    /// external stages share a process group that owns the terminal until
+    /// they exit or stop; a stopped pipeline becomes a job. Every stage's
+    /// status ends up in `PIPESTATUS`, and the CPU time of its processes in
+    /// `children`.
+    fn run_stages(&self, pipeline: &Pipeline, children: &mut ChildTimes) -> Result<ShellStatus, String> {
+        let exec_in_place = self.exec_in_place.take();
+        let mut pgid = 0;
+        let mut pids = Vec::new();
+        // One entry per stage; `None` for processes still to be waited on.
+        let mut statuses: Vec<Option<i32>> = Vec::new();
+        let mut outcome = Ok(ShellStatus::Continue);
 
         let mut previous_output = PipeState::None;
-        let mut iter = pipeline.iter().peekable();
+        let mut iter = pipeline.commands.iter().peekable();
 
         while let Some(cmd) = iter.next() {
             let is_last = iter.peek().is_none();
-            
-            let is_builtin = self.registry.get_builtin(&cmd.command).is_some();
 
-            let (new_state, status) = if is_builtin {
-                self.handle_builtin(cmd, &mut previous_output, is_last)?
+            if let Some(function) = &cmd.function {
+                self.registry.define_function(&function.name, function.body.clone());
+                self.registry.set_last_status(0);
+                statuses.push(Some(0));
+                previous_output = PipeState::None;
+                continue;
+            }
+
+            let argv = match expand_words(&cmd.words, self.registry) {
+                Ok(argv) => argv,
+                Err(e) => {
+                    statuses.push(Some(1));
+                    outcome = Err(e);
+                    break;
+                }
+            };
+            let Some((name, args)) = argv.split_first() else {
+                let result = self.handle_assignments(cmd);
+                statuses.push(Some(self.registry.get_last_status()));
+                if let Err(e) = result {
+                    outcome = Err(e);
+                    break;
+                }
+                previous_output = PipeState::None;
+                continue;
+            };
+
+            let (mut name, mut args) = (name, args);
+            let mut runs_in_shell = self.registry.get_function(name).is_some() || self.registry.get_builtin(name).is_some();
+
+            // `command name` (or `builtin command name`) for an external command
+            // runs it as a stage of its own, passing over any function of that
+            // name.
+            let resolved;
+            if matches!(name.as_str(), "command" | "builtin") && self.registry.get_function(name).is_none() {
+                match self.external_command(name, args) {
+                    Ok(Some((path, rest))) => {
+                        resolved = path;
+                        (name, args, runs_in_shell) = (&resolved, rest, false);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        self.registry.set_last_status(127);
+                        statuses.push(Some(127));
+                        outcome = Err(e);
+                        break;
+                    }
+                }
+            }
+
+            // An unknown command goes to `command_not_found_handle`, if defined,
+            // with the command line as its arguments.
+            let (name, args) = if !runs_in_shell && self.registry.get_function(NOT_FOUND_HANDLER).is_some() && self.is_missing_command(name) {
+                runs_in_shell = true;
+                (NOT_FOUND_HANDLER, argv.as_slice())
+            } else {
+                (name.as_str(), args)
+            };
+
+            // Assume failure until the stage reports otherwise, so errors raised
+            // before a process even starts still leave a non-zero `$?`.
+            self.registry.set_last_status(1);
+
+            let result = if runs_in_shell && !is_last {
+                self.fork_builtin(name, args, cmd, &mut previous_output, pgid)
+            } else if runs_in_shell {
+                self.handle_builtin(name, args, cmd, &mut previous_output)
             } else {
-                self.handle_external(cmd, &mut previous_output, is_last)?
+                let output = match (is_last, exec_in_place) {
+                    (false, _) => StageOutput::Pipe,
+                    (true, false) => StageOutput::Inherit,
+                    (true, true) => StageOutput::Exec,
+                };
+                self.handle_external(name, args, cmd, &mut previous_output, output, pgid)
             };
+            self.registry.set_last_argument(argv.last().map_or(name, String::as_str));
 
-            if let ShellStatus::Exit = status {
-                return Ok(ShellStatus::Exit);
+            let (new_state, status) = match result {
+                Ok(stage) => stage,
+                Err(e) => {
+                    statuses.push(Some(self.registry.get_last_status()));
+                    outcome = Err(e);
+                    break;
+                }
+            };
+
+            if let Some(pid) = new_state.pid() {
+                if pgid == 0 {
+                    pgid = pid;
+                }
+                pids.push(pid);
+                statuses.push(None);
+            } else {
+                statuses.push(Some(self.registry.get_last_status()));
+            }
+
+            if status.stops_execution() {
+                outcome = Ok(status);
+                break;
             }
 
             previous_output = new_state;
         }
+        drop(previous_output);
+
+        let mut stopped = false;
+        if !pids.is_empty() {
+            let mut job = Job::new(pgid, &pids, format_pipeline(pipeline));
+            job.wait_in_foreground(self.registry.has_job_control(), false);
+            *children += job.child_times();
+
+            let interactive = self.registry.is_interactive();
+            if let Some(message) = job.termination_message()
+                && (interactive || !message.is_empty()) {
+                eprintln!("{}", message);
+            }
+            if !interactive && job.signal() == Some(libc::SIGINT) && take_wait_interrupt() {
+                self.run_exit_trap(128 + libc::SIGINT);
+                exit_on_interrupt();
+            }
+
+            let mut codes = job.statuses().into_iter();
+            for status in statuses.iter_mut().filter(|status| status.is_none()) {
+                *status = codes.next();
+            }
+
+            if job.state() == JobState::Stopped {
+                eprintln!("\n{}", self.registry.jobs().add_stopped(job));
+                stopped = true;
+            }
+        }
+
+        let statuses: Vec<i32> = statuses.into_iter().map(|status| status.unwrap_or(0)).collect();
+        if let Some(&last) = statuses.last() {
+            let status = if stopped {
+                128 + libc::SIGTSTP
+            } else if self.registry.is_option_set("pipefail") {
+                statuses.iter().rev().find(|&&status| status != 0).copied().unwrap_or(0)
+            } else {
+                last
+            };
+            self.registry.set_last_status(status);
+            let _ = self.registry.set_array("PIPESTATUS", statuses.iter().map(|status| (None, status.to_string())).collect());
+        }
+
+        outcome
+    }
 
-        if let PipeState::Process(mut child) = previous_output {
-             child.wait().map_err(|e| e.to_string())?;
+    fn handle_assignments(&self, cmd: &ParsedCommand) -> Result<(), String> {
+        self.registry.set_last_status(0);
+
+        for assignment in &cmd.assignments {
+            if let Err(e) = self.assign(assignment) {
+                self.registry.set_last_status(1);
+                return Err(e);
+            }
         }
 
-        Ok(ShellStatus::Continue)
+        // The files are still created (or checked), as `> file` truncates one.
+        self.redirections(cmd, &mut PipeState::None)?;
+        Ok(())
     }
 
+    fn assign(&self, assignment: &Assignment) -> Result<(), String> {
+        let name = &assignment.name;
 
-    fn handle_builtin(
-        &self, 
-        cmd: &ParsedCommand, 
-        _input: &mut PipeState,
-        is_last: bool
+        match (&assignment.value, &assignment.subscript) {
+            (AssignmentValue::Scalar(value), None) => {
+                let value = expand_to_string(value, self.registry)?;
+                self.trace(&format!("{}={}", name, quote(&value)));
+                self.registry.set_variable(name, &value)
+            }
+            (AssignmentValue::Scalar(value), Some(subscript)) => {
+                let subscript = expand_to_string(subscript, self.registry)?;
+                let value = expand_to_string(value, self.registry)?;
+                self.trace(&format!("{}[{}]={}", name, subscript, quote(&value)));
+                self.registry.set_array_element(name, &subscript, &value)
+            }
+            (AssignmentValue::Array(elements), _) => {
+                let mut values = Vec::new();
+                for element in elements {
+                    match &element.key {
+                        Some(key) => values.push((
+                            Some(expand_to_string(key, self.registry)?),
+                            expand_to_string(&element.value, self.registry)?,
+                        )),
+                        None => values.extend(expand_word(&element.value, self.registry)?.into_iter().map(|value| (None, value))),
+                    }
+                }
+                let elements: Vec<String> = values.iter()
+                    .map(|(key, value)| match key {
+                        Some(key) => format!("[{}]={}", key, quote(value)),
+                        None => quote(value),
+                    })
+                    .collect();
+                self.trace(&format!("{}=({})", name, elements.join(" ")));
+                self.registry.set_array(name, values)
+            }
+        }
+    }
+
+    /// Runs a shell function with `args` as its positional parameters. The
+    /// body's commands use the shell's own descriptors, so redirections
+    /// are applied to those for the length of the call.
+    fn handle_function(
+        &self,
+        name: &str,
+        body: &[Pipeline],
+        args: &[String],
+        cmd: &ParsedCommand,
+        input: &mut PipeState,
     ) -> Result<(PipeState, ShellStatus), String> {
-        
-        let builtin = self.registry.get_builtin(&cmd.command).unwrap();
-        
-        let mut output_buffer = Vec::new();
-        let mut writer: Box<dyn Write> = if let Some(path) = &cmd.stdout_redirect {
-            let file = open_file(path, cmd.stdout_redirect_append)?;
-            Box::new(file)
-        } else if !is_last {
-            Box::new(&mut output_buffer)
-        } else {
-            Box::new(io::stdout())
+        let redirections = self.redirections(cmd, input)?;
+        let saved = self.apply_temporary_assignments(cmd)?;
+        self.trace_command(name, args);
+        let saved_fds = SavedFds::redirect(&redirections.moves);
+        let saved_params = self.registry.set_positional_params(args.to_vec());
+        self.registry.enter_function();
+        let caller_call = self.registry.traps().begin_call();
+
+        let mut status = self.run_pipelines(body);
+
+        // Functions don't inherit the RETURN trap, so it only runs for one
+        // set by this call.
+        let trap_set_here = self.registry.traps().end_call(caller_call);
+        if trap_set_here && (!status.stops_execution() || matches!(status, ShellStatus::Return(_))) {
+            let trap_status = self.run_trap(TrapEvent::Return);
+            if trap_status.stops_execution() {
+                status = trap_status;
+            }
+        }
+
+        self.registry.leave_function();
+        self.registry.set_positional_params(saved_params);
+        self.restore_assignments(saved);
+        drop(saved_fds);
+
+        let status = match status {
+            ShellStatus::Exit(code) => ShellStatus::Exit(code),
+            ShellStatus::Return(code) | ShellStatus::Status(code) => ShellStatus::Status(code),
+            ShellStatus::Continue => ShellStatus::Status(self.registry.get_last_status()),
         };
+        if let ShellStatus::Status(code) = status {
+            self.registry.set_last_status(code);
+        }
+        Ok((PipeState::None, status))
+    }
 
-        if let Some(path) = &cmd.stderr_redirect {
-            let _ = open_file(path, cmd.stderr_redirect_append)?;
+    /// Opens a command's redirections, after input from the previous stage,
+    /// as descriptor moves to apply in the order they were written.
+    fn redirections(&self, cmd: &ParsedCommand, input: &mut PipeState) -> Result<FdRedirections, String> {
+        let mut redirections = FdRedirections::default();
+        if let Some(output) = input.take_output() {
+            redirections.open(0, output)?;
         }
 
-        let result = builtin.execute(&cmd.args, self.registry, &mut *writer);
+        for redirect in &cmd.redirects {
+            match redirect {
+                FdRedirect::Open { fd, operator, path } => {
+                    let path = expand_to_string(path, self.registry)?;
+                    let file = match *operator {
+                        "<" => open_input_file(&path)?,
+                        _ => self.open_output(&path, *operator == ">>")?,
+                    };
+                    redirections.open(*fd, file.into())?;
+                }
+                FdRedirect::Duplicate { fd, target } => {
+                    // The target may be opened by an earlier redirection of this command.
+                    let is_open = redirections.moves.iter().any(|&(fd, source)| fd == *target && source >= 0)
+                        || unsafe { libc::fcntl(*target, libc::F_GETFD) } >= 0;
+                    if !is_open {
+                        return Err(format!("{}: Bad file descriptor", target));
+                    }
+                    redirections.moves.push((*fd, *target));
+                }
+                FdRedirect::Close { fd } => redirections.moves.push((*fd, -1)),
+            }
+        }
+        Ok(redirections)
+    }
 
-        drop(writer); 
+    /// The external command that `command [-p] name args` stands for, with
+    /// the file found in the default `PATH` for `-p`. `None` leaves the rest
+    /// to the `command` and `builtin` builtins: lookups, builtins and bad
+    /// options.
+    fn external_command<'b>(&self, name: &str, args: &'b [String]) -> Result<Option<(String, &'b [String])>, String> {
+        if name == "builtin" {
+            let args = args.strip_prefix(&["--".to_string()]).unwrap_or(args);
+            return match args.split_first() {
+                Some((next, rest)) if next == "command" || next == "builtin" => self.external_command(next, rest),
+                _ => Ok(None),
+            };
+        }
 
-        match result {
-            Ok(status) => {
-                if !is_last && cmd.stdout_redirect.is_none() {
-                    Ok((PipeState::Buffer(output_buffer), status))
-                } else {
-                    Ok((PipeState::None, status))
+        let mut default_path = false;
+        let mut iter = args.iter().enumerate().peekable();
+        while let Some((_, flags)) = iter.next_if(|(_, arg)| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            // A restricted shell's `command` refuses `-p`.
+            if flags.chars().skip(1).any(|flag| flag != 'p') || self.registry.is_option_set("restricted") {
+                return Ok(None);
+            }
+            default_path = true;
+        }
+
+        let Some((index, name)) = iter.next() else {
+            return Ok(None);
+        };
+        if name == "command" || name == "builtin" {
+            return self.external_command(name, &args[index + 1..]);
+        }
+        if self.registry.get_builtin(name).is_some() {
+            return Ok(None);
+        }
+        let name = match default_path && !name.contains('/') {
+            true => find_in_default_path(name).ok_or_else(|| format!("{}: command not found", name))?,
+            false => name.clone(),
+        };
+        Ok(Some((name, &args[index + 1..])))
+    }
+
+    /// Whether `name` would fail with "command not found".
+    fn is_missing_command(&self, name: &str) -> bool {
+        !name.contains('/') && self.registry.get_executable_path(name).is_none()
+    }
+
+    /// Opens the file of an output redirection, which a restricted shell
+    /// refuses.
+    fn open_output(&self, path: &str, append: bool) -> Result<File, String> {
+        if self.registry.is_option_set("restricted") {
+            return Err(format!("{}: restricted: cannot redirect output", path));
+        }
+        open_file(path, append)
+    }
+
+
+    /// Runs a builtin that feeds a later pipeline stage in a forked subshell
+    /// whose stdout is a pipe, so it streams alongside the other stages
+    /// instead of running to completion first.
+    fn fork_builtin(
+        &self,
+        name: &str,
+        args: &[String],
+        cmd: &ParsedCommand,
+        input: &mut PipeState,
+        pgid: i32,
+    ) -> Result<(PipeState, ShellStatus), String> {
+        // Close-on-exec, so commands started while the pipe is open don't
+        // hold its write end and keep the reader from seeing end of file.
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(format!("pipe: {}", io::Error::last_os_error()));
+        }
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+        let stdin = input.take_output();
+        let job_control = self.registry.has_job_control();
+
+        let pid = self.fork_subshell(|| {
+            unsafe {
+                // Rust ignores SIGPIPE; a stage whose reader went away should
+                // just die quietly, like an external command would.
+                libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+                if job_control {
+                    libc::setpgid(0, if pgid == 0 { libc::getpid() } else { pgid });
                 }
-            },
-            Err(e) => {
-                if let Some(path) = &cmd.stderr_redirect {
-                    let mut file = open_file(path, true)?; // Forçamos append aqui para não truncar o que acabámos de criar
-                    writeln!(file, "{}", e).map_err(|e| e.to_string())?;
-                    Ok((PipeState::None, ShellStatus::Continue))
-                } else {
-                    Err(e)
+                libc::close(read_fd);
+                libc::dup2(write_fd, 1);
+                libc::close(write_fd);
+                if let Some(stdin) = &stdin {
+                    libc::dup2(stdin.as_raw_fd(), 0);
+                }
+            }
+            drop(stdin);
+
+            match self.handle_builtin(name, args, cmd, &mut PipeState::None) {
+                Ok((_, status)) if status.stops_execution() => status,
+                Ok(_) => ShellStatus::Continue,
+                Err(e) => {
+                    self.report_error(&e);
+                    ShellStatus::Status(1)
                 }
             }
+        });
+
+        unsafe { libc::close(write_fd) };
+        let output = unsafe { File::from_raw_fd(read_fd) };
+        let pid = pid?;
+
+        if job_control {
+            unsafe { libc::setpgid(pid, if pgid == 0 { pid } else { pgid }) };
+        }
+
+        Ok((PipeState::Subshell { pid, output: Some(output) }, ShellStatus::Continue))
+    }
+
+    /// Runs a builtin (or function) in the current shell. Input from the
+    /// previous stage and the command's redirections are applied to the
+    /// shell's own descriptors while it runs, so it reads and writes
+    /// through them like an external command would.
+    fn handle_builtin(
+        &self,
+        name: &str,
+        args: &[String],
+        cmd: &ParsedCommand,
+        input: &mut PipeState,
+    ) -> Result<(PipeState, ShellStatus), String> {
+        if let Some(body) = self.registry.get_function(name) {
+            return self.handle_function(name, &body, args, cmd, input);
+        }
+        let builtin = self.registry.get_builtin(name).unwrap();
+        let redirections = self.redirections(cmd, input)?;
+
+        // `exec` applies its redirections to the shell itself, for good.
+        let permanent = name == "exec";
+        if permanent {
+            let _ = io::stdout().flush();
+            redirections.apply();
+        }
+
+        let saved = self.apply_temporary_assignments(cmd)?;
+        self.trace_command(name, args);
+        let saved_fds = SavedFds::redirect(if permanent { &[] } else { &redirections.moves });
+        let result = builtin.execute(args, self.registry, &mut RawStdin, &mut io::stdout(), &mut io::stderr());
+        self.restore_assignments(saved);
+
+        self.registry.set_last_status(match result {
+            Ok(ShellStatus::Status(code) | ShellStatus::Return(code) | ShellStatus::Exit(code)) => code,
+            Ok(ShellStatus::Continue) => 0,
+            Err(_) => 1,
+        });
+
+        // An error goes to the command's stderr if that was redirected;
+        // otherwise it's reported like any other.
+        let stderr_redirected = !permanent && redirections.moves.iter().any(|&(fd, _)| fd == 2);
+        let result = match result {
+            Err(e) if stderr_redirected => {
+                eprintln!("{}", e);
+                Ok(ShellStatus::Continue)
+            }
+            result => result,
+        };
+        drop(saved_fds);
+        result.map(|status| (PipeState::None, status))
+    }
+
+    /// `set -x` shows each command and assignment, once expanded, on stderr
+    /// after the expanded `PS4`.
+    fn trace(&self, line: &str) {
+        if !self.registry.is_option_set("xtrace") {
+            return;
+        }
+        let ps4 = self.registry.get_variable("PS4").unwrap_or_else(|| "+ ".to_string());
+        let prefix = expand_to_string(&parse_prompt(&ps4), self.registry).unwrap_or(ps4);
+        eprintln!("{}{}", prefix, line);
+    }
+
+    fn trace_command(&self, name: &str, args: &[String]) {
+        if self.registry.is_option_set("xtrace") {
+            let words: Vec<String> = std::iter::once(name).chain(args.iter().map(String::as_str)).map(quote).collect();
+            self.trace(&words.join(" "));
+        }
+    }
+
+    /// Prefix assignments (`VAR=x builtin`) only last for the builtin call, so
+    /// the previous values are handed back for `restore_assignments`.
+    fn apply_temporary_assignments(&self, cmd: &ParsedCommand) -> Result<Vec<(String, Option<String>)>, String> {
+        let mut saved = Vec::new();
+        for assignment in &cmd.assignments {
+            saved.push((assignment.name.clone(), self.registry.get_variable(&assignment.name)));
+            self.assign(assignment)?;
+        }
+        Ok(saved)
+    }
+
+    fn restore_assignments(&self, saved: Vec<(String, Option<String>)>) {
+        for (name, value) in saved.into_iter().rev() {
+            match value {
+                Some(value) => { let _ = self.registry.set_variable(&name, &value); },
+                None => self.registry.unset_variable(&name),
+            }
         }
     }
 
 
     fn handle_external(
         &self,
+        name: &str,
+        args: &[String],
         cmd: &ParsedCommand,
         input: &mut PipeState,
-        is_last: bool
+        output: StageOutput,
+        pgid: i32,
     ) -> Result<(PipeState, ShellStatus), String> {
-        
-        let full_path = match self.registry.get_executable_path(&cmd.command) {
-            Some(p) => p,
-            None => return Err(format!("{}: command not found", cmd.command)),
+        // A restricted shell only runs what it finds in `PATH`.
+        if name.contains('/') && self.registry.is_option_set("restricted") {
+            return Err(format!("{}: restricted: cannot specify `/' in command names", name));
+        }
+        let full_path = self.resolve_command(name)?;
+
+        // The pipes are set up first; the command's own redirections then
+        // apply on top of them, in order, just before it execs.
+        let redirections = self.redirections(cmd, &mut PipeState::None)?;
+        let stdin = match (input.take_output(), input) {
+            (Some(output), _) => Stdio::from(output),
+            (None, PipeState::None) => Stdio::inherit(),
+            (None, _) => Stdio::null(),
         };
+        let stdout = if output == StageOutput::Pipe { Stdio::piped() } else { Stdio::inherit() };
 
-        let stdin = match input {
-            PipeState::Process(child) => {
-                if let Some(out) = child.stdout.take() {
-                    Stdio::from(out)
-                } else {
-                    Stdio::null()
+        let mut environment = self.registry.get_exported_variables();
+        for assignment in &cmd.assignments {
+            if let (AssignmentValue::Scalar(value), None) = (&assignment.value, &assignment.subscript) {
+                // Like bash, the command still runs without the variable.
+                if let Err(e) = self.registry.check_writable(&assignment.name) {
+                    self.report_error(&e);
+                    continue;
                 }
-            },
-            PipeState::Buffer(_) => {
-                Stdio::piped() 
-            },
-            PipeState::None => Stdio::inherit(),
-        };
+                let value = expand_to_string(value, self.registry)?;
+                self.trace(&format!("{}={}", assignment.name, quote(&value)));
+                environment.push((assignment.name.clone(), value));
+            }
+        }
+        self.trace_command(name, args);
 
-        let (stdout, creates_pipe) = if let Some(path) = &cmd.stdout_redirect {
-            let file = open_file(path, cmd.stdout_redirect_append)?;
-            (Stdio::from(file), false)
-        } else if !is_last {
-            (Stdio::piped(), true)
-        } else {
-            (Stdio::inherit(), false)
+        let mut command_builder = process_command(&full_path, name)?;
+
+        command_builder
+            .args(args)
+            .env_clear()
+            .envs(environment)
+            .stdin(stdin)
+            .stdout(stdout);
+
+        // With job control each stage joins the pipeline's process group (the
+        // first one starts it) and takes the terminal before running.
+        let job_control = self.registry.has_job_control();
+        let moves = redirections.moves.clone();
+        let ignored = self.registry.traps().ignored_signals();
+        unsafe {
+            command_builder.pre_exec(move || {
+                // Runs after the pipes are in place.
+                apply_moves(&moves);
+                if job_control {
+                    let group = if pgid == 0 { libc::getpid() } else { pgid };
+                    libc::setpgid(0, group);
+                    libc::tcsetpgrp(0, group);
+                }
+                restore_default_signals(&ignored);
+                Ok(())
+            });
+        }
+
+        let failed = |e: io::Error| {
+            let (status, reason) = match e.kind() {
+                ErrorKind::NotFound => (127, "No such file or directory".to_string()),
+                ErrorKind::PermissionDenied => (126, "Permission denied".to_string()),
+                _ => (126, e.to_string()),
+            };
+            self.registry.set_last_status(status);
+            format!("{}: {}", name, reason)
         };
+        if output == StageOutput::Exec {
+            // Only returns if the exec failed.
+            return Err(failed(command_builder.exec()));
+        }
+        let child = command_builder.spawn().map_err(failed)?;
 
-        let stderr = if let Some(path) = &cmd.stderr_redirect {
-            let file = open_file(path, cmd.stderr_redirect_append)?;
-            Stdio::from(file)
-        } else {
-            Stdio::inherit()
+        if job_control {
+            let pid = child.id() as i32;
+            unsafe { libc::setpgid(pid, if pgid == 0 { pid } else { pgid }) };
+        }
+
+        Ok((PipeState::Process(child), ShellStatus::Continue))
+    }
+
+    /// Finds the file to run for a command. Names with a slash are used as
+    /// given, and get a specific error when they can't be executed.
+    fn resolve_command(&self, name: &str) -> Result<String, String> {
+        let failure = |status: i32, reason: &str| {
+            self.registry.set_last_status(status);
+            Err(format!("{}: {}", name, reason))
         };
 
-        let mut command_builder = ProcessCommand::new(&full_path);
-        
-        command_builder
-            .arg0(&cmd.command) 
-            .args(&cmd.args)
-            .stdin(stdin)
-            .stdout(stdout)
-            .stderr(stderr);
+        if !name.contains('/') {
+            return match self.registry.get_executable_path(name) {
+                Some(path) => {
+                    self.registry.command_cache().record_hit(name);
+                    Ok(path)
+                }
+                None => {
+                    let candidates = self.registry.get_command_names();
+                    match closest_matches(name, &candidates).as_slice() {
+                        [] => failure(127, "command not found"),
+                        matches => failure(127, &format!("command not found. Did you mean: {}?", matches.join(", "))),
+                    }
+                }
+            };
+        }
+
+        match fs::metadata(name) {
+            Err(e) if e.kind() == ErrorKind::NotFound => failure(127, "No such file or directory"),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => failure(126, "Permission denied"),
+            Err(e) => failure(126, &e.to_string()),
+            Ok(metadata) if metadata.is_dir() => failure(126, "Is a directory"),
+            Ok(metadata) if metadata.permissions().mode() & 0o111 == 0 => failure(126, "Permission denied"),
+            Ok(_) => Ok(name.to_string()),
+        }
+    }
+}
+
+/// Builds the command that runs the file at `path` as `name`. A text file
+/// without a `#!` line is a script for this shell.
+pub(super) fn process_command(path: &str, name: &str) -> Result<ProcessCommand, String> {
+    if is_shell_script(path) {
+        let mut builder = ProcessCommand::new(env::current_exe().map_err(|e| e.to_string())?);
+        builder.arg(path);
+        Ok(builder)
+    } else {
+        let mut builder = ProcessCommand::new(path);
+        builder.arg0(name);
+        Ok(builder)
+    }
+}
+
+/// Whether the kernel would refuse to run a file that is really a shell
+/// script: it has no `#!` line, isn't an ELF binary and, like bash checks,
+/// has no NUL bytes near the start.
+fn is_shell_script(path: &str) -> bool {
+    let mut head = Vec::new();
+    if File::open(path).and_then(|file| file.take(80).read_to_end(&mut head)).is_err() {
+        return false;
+    }
+    !head.starts_with(b"#!") && !head.starts_with(b"\x7fELF") && !head.contains(&0)
+}
 
-        let mut child = command_builder
-            .spawn()
-            .map_err(|e| format!("Failed to start {}: {}", cmd.command, e))?;
+/// The function run in place of a command that can't be found.
+const NOT_FOUND_HANDLER: &str = "command_not_found_handle";
 
-        
-        if let PipeState::Buffer(data) = input {
-            if let Some(mut stdin) = child.stdin.take() {
-                let _ = stdin.write_all(data); 
+/// Redirections ready to apply to descriptors: `(fd, source)` pairs in
+/// order, where a source of -1 closes `fd`, and the files they use.
+#[derive(Default)]
+struct FdRedirections {
+    files: Vec<OwnedFd>,
+    moves: Vec<(i32, i32)>,
+}
+
+impl FdRedirections {
+    /// Adds `file` as the new `fd`. The file is moved above the descriptors
+    /// scripts usually name, so that applying one redirection can't
+    /// clobber the source of the next.
+    fn open(&mut self, fd: i32, file: OwnedFd) -> Result<(), String> {
+        let copy = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 10) };
+        if copy < 0 {
+            return Err(format!("{}: {}", fd, io::Error::last_os_error()));
+        }
+        let file = unsafe { OwnedFd::from_raw_fd(copy) };
+        self.moves.push((fd, file.as_raw_fd()));
+        self.files.push(file);
+        Ok(())
+    }
+
+    /// Applies the redirections for good, as `exec` does.
+    fn apply(&self) {
+        apply_moves(&self.moves);
+    }
+}
+
+/// Only makes system calls, so it is safe to run between fork and exec.
+fn apply_moves(moves: &[(i32, i32)]) {
+    for &(fd, source) in moves {
+        unsafe {
+            if source < 0 {
+                libc::close(fd);
+            } else {
+                libc::dup2(source, fd);
             }
         }
+    }
+}
 
-        if creates_pipe {
-            Ok((PipeState::Process(child), ShellStatus::Continue))
-        } else {
-            child.wait().map_err(|e| e.to_string())?;
-            Ok((PipeState::None, ShellStatus::Continue))
+/// The shell's own descriptors, saved while a command's redirections
+/// replace them and put back when dropped.
+struct SavedFds(Vec<(i32, i32)>);
+
+impl SavedFds {
+    /// Applies `moves` in order, keeping a copy of what each descriptor
+    /// was (or -1 if it was closed).
+    fn redirect(moves: &[(i32, i32)]) -> Self {
+        let _ = io::stdout().flush();
+        let saved = moves.iter().map(|&(fd, source)| {
+            let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 10) };
+            apply_moves(&[(fd, source)]);
+            (fd, copy)
+        }).collect();
+        SavedFds(saved)
+    }
+}
+
+impl Drop for SavedFds {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        for &(fd, copy) in self.0.iter().rev() {
+            unsafe {
+                if copy < 0 {
+                    libc::close(fd);
+                } else {
+                    libc::dup2(copy, fd);
+                    libc::close(copy);
+                }
+            }
         }
     }
+}
+
+/// Converts a child's exit status into a `$?` value, using `128 + signal`
+/// for processes killed by a signal.
+pub(super) fn status_code(status: ExitStatus) -> i32 {
+    status.code().unwrap_or_else(|| 128 + status.signal().unwrap_or(0))
 }
\ No newline at end of file
@@ -1,16 +1,20 @@
-use std::{env, io::Write};
+use std::io::{Read, Write};
 
-use super::{{Command, ShellStatus, CommandRegistry}};
+use super::{{Command, ShellStatus, CommandRegistry, ShellExecutor}};
 
 pub struct ExitCommand;
 
 impl Command for ExitCommand {
-    fn execute(&self, _: &[String], registry: &CommandRegistry, _: &mut dyn Write) -> Result<ShellStatus, String> {
-        let path_hist = env::var("HISTFILE").unwrap_or_default();
-        if path_hist != "" {
-            let _ = registry.write_history_to_file(&path_hist, false, false);
-        }
-        Ok(ShellStatus::Exit)
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let code = match args.first() {
+            Some(arg) => arg.parse::<i32>()
+                .map_err(|_| format!("exit: {}: numeric argument required", arg))?,
+            None => registry.get_last_status(),
+        };
+
+        registry.save_history();
+        let code = ShellExecutor::new(registry).run_exit_trap(code & 0xff);
+        Ok(ShellStatus::Exit(code & 0xff))
     }
 
     fn get_name(&self) -> &str {
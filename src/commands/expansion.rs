@@ -0,0 +1,635 @@
+use super::CommandRegistry;
+use crate::utils::{Parameter, ParameterForm, ParameterOp, Pattern, ReplaceMode, Subscript, Word, WordPart};
+use crate::utils::{GlobOptions, escape_pattern, expand_glob, has_glob, is_valid_name};
+use super::ShellExecutor;
+
+const DEFAULT_IFS: &str = " \t\n";
+
+/// Accumulates the fields produced while expanding one word. A word can
+/// yield zero fields (`"$@"` with no positional parameters, an unquoted
+/// empty expansion) or several.
+#[derive(Default)]
+struct Fields {
+    fields: Vec<String>,
+    /// Each field as a glob pattern, with the quoted characters escaped.
+    patterns: Vec<String>,
+    current: String,
+    current_pattern: String,
+    has_current: bool,
+    /// Characters unquoted expansions are split on; empty disables splitting.
+    ifs: String,
+    /// The current field was just ended by IFS whitespace, which absorbs one
+    /// following non-whitespace IFS character.
+    ended_by_whitespace: bool,
+}
+
+impl Fields {
+    fn splitting(registry: &CommandRegistry) -> Self {
+        let ifs = registry.get_variable("IFS").unwrap_or_else(|| DEFAULT_IFS.to_string());
+        Fields { ifs, ..Fields::default() }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        self.current.push_str(text);
+        self.current_pattern.push_str(&escape_pattern(text));
+        self.has_current = true;
+        self.ended_by_whitespace = false;
+    }
+
+    /// Appends unquoted text that isn't split, such as a literal part of the
+    /// word, whose glob characters stay active.
+    fn push_unquoted(&mut self, text: &str) {
+        self.current.push_str(text);
+        self.current_pattern.push_str(text);
+        self.has_current = true;
+        self.ended_by_whitespace = false;
+    }
+
+    fn push_list(&mut self, values: &[String]) {
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.finish();
+            }
+            self.push_str(value);
+        }
+    }
+
+    fn push(&mut self, expanded: &Expanded) {
+        match expanded {
+            Expanded::Text(text) => self.push_str(text),
+            Expanded::List(values) => self.push_list(values),
+        }
+    }
+
+    /// Appends the result of an unquoted expansion, splitting it on IFS. Runs
+    /// of IFS whitespace separate fields but never produce empty ones; other
+    /// IFS characters each end a field, even an empty one.
+    fn push_split(&mut self, text: &str) {
+        for c in text.chars() {
+            if !self.ifs.contains(c) {
+                self.current.push(c);
+                self.current_pattern.push(c);
+                self.has_current = true;
+                self.ended_by_whitespace = false;
+            } else if DEFAULT_IFS.contains(c) {
+                if self.has_current {
+                    self.finish();
+                    self.ended_by_whitespace = true;
+                }
+            } else if self.ended_by_whitespace {
+                self.ended_by_whitespace = false;
+            } else {
+                self.end_field();
+            }
+        }
+    }
+
+    fn push_split_list(&mut self, values: &[String]) {
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.finish();
+            }
+            self.push_split(value);
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.has_current {
+            self.end_field();
+        }
+    }
+
+    fn end_field(&mut self) {
+        self.fields.push(std::mem::take(&mut self.current));
+        self.patterns.push(std::mem::take(&mut self.current_pattern));
+        self.has_current = false;
+    }
+
+    fn into_fields(mut self) -> Vec<String> {
+        self.finish();
+        self.fields
+    }
+
+    /// The fields after pathname expansion: a field with unquoted glob
+    /// characters becomes the files it matches. Without a match it stays as
+    /// it is, unless `nullglob` or `failglob` say otherwise.
+    fn into_globbed(mut self, registry: &CommandRegistry) -> Result<Vec<String>, String> {
+        self.finish();
+        if registry.is_option_set("noglob") {
+            return Ok(self.fields);
+        }
+
+        let options = GlobOptions {
+            dotglob: registry.is_option_set("dotglob"),
+            nocaseglob: registry.is_option_set("nocaseglob"),
+        };
+        let mut expanded = Vec::new();
+        for (field, pattern) in self.fields.into_iter().zip(self.patterns) {
+            if !has_glob(&pattern) {
+                expanded.push(field);
+                continue;
+            }
+            let matches = expand_glob(&pattern, options);
+            if !matches.is_empty() {
+                expanded.extend(matches);
+            } else if registry.is_option_set("failglob") {
+                return Err(format!("no match: {}", field));
+            } else if !registry.is_option_set("nullglob") {
+                expanded.push(field);
+            }
+        }
+        Ok(expanded)
+    }
+}
+
+/// What a single parameter expands to before it is merged into the word.
+enum Expanded {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl Expanded {
+    fn is_null(&self) -> bool {
+        match self {
+            Expanded::Text(text) => text.is_empty(),
+            Expanded::List(values) => values.iter().all(|value| value.is_empty()),
+        }
+    }
+
+    fn map(self, transform: impl Fn(&str) -> String) -> Expanded {
+        match self {
+            Expanded::Text(text) => Expanded::Text(transform(&text)),
+            Expanded::List(values) => Expanded::List(values.iter().map(|value| transform(value)).collect()),
+        }
+    }
+}
+
+pub fn expand_words(words: &[Word], registry: &CommandRegistry) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    for word in words {
+        fields.extend(expand_word(word, registry)?);
+    }
+    Ok(fields)
+}
+
+pub fn expand_word(word: &Word, registry: &CommandRegistry) -> Result<Vec<String>, String> {
+    let mut fields = Fields::splitting(registry);
+    expand_parts(&word.parts, registry, &mut fields, false)?;
+    fields.into_globbed(registry)
+}
+
+/// Expands a word where only a single string makes sense, such as an
+/// assignment value or a redirection target. No field splitting happens.
+pub fn expand_to_string(word: &Word, registry: &CommandRegistry) -> Result<String, String> {
+    let mut fields = Fields::default();
+    expand_parts(&word.parts, registry, &mut fields, false)?;
+    Ok(fields.into_fields().join(" "))
+}
+
+/// `nested` is set for the operand of an unquoted `${...}` operator, whose
+/// unquoted text is itself subject to field splitting.
+fn expand_parts(parts: &[WordPart], registry: &CommandRegistry, fields: &mut Fields, nested: bool) -> Result<(), String> {
+    for part in parts {
+        match part {
+            WordPart::Literal(text) if nested => fields.push_split(text),
+            WordPart::Literal(text) => fields.push_unquoted(text),
+            WordPart::Quoted(text) => fields.push_str(text),
+            WordPart::Parameter(parameter) => expand_parameter(parameter, registry, fields)?,
+            WordPart::Command { source, quoted } => {
+                let output = ShellExecutor::new(registry).capture_output(source)?;
+                if *quoted {
+                    fields.push_str(&output);
+                } else {
+                    fields.push_split(&output);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn expand_parameter(parameter: &Parameter, registry: &CommandRegistry, fields: &mut Fields) -> Result<(), String> {
+    let value = parameter_value(parameter, registry)?;
+    check_bound(parameter, &value, registry)?;
+
+    let Some(op) = &parameter.op else {
+        push_value(fields, parameter, value.unwrap_or(Expanded::Text(String::new())), registry);
+        return Ok(());
+    };
+
+    let is_unset = |check_null: bool| value.as_ref().is_none_or(|value| check_null && value.is_null());
+
+    match op {
+        ParameterOp::Default { word, check_null } => match is_unset(*check_null) {
+            true => expand_operand(parameter, word, registry, fields)?,
+            false => push_value(fields, parameter, value.unwrap(), registry),
+        },
+        ParameterOp::Alternative { word, check_null } => match is_unset(*check_null) {
+            true if parameter.quoted => fields.push_str(""),
+            true => {}
+            false => expand_operand(parameter, word, registry, fields)?,
+        },
+        ParameterOp::Assign { word, check_null } => match is_unset(*check_null) {
+            true => {
+                let text = expand_to_string(word, registry)?;
+                assign_parameter(parameter, &text, registry)?;
+                push_value(fields, parameter, Expanded::Text(text), registry);
+            }
+            false => push_value(fields, parameter, value.unwrap(), registry),
+        },
+        ParameterOp::Error { word, check_null } => match is_unset(*check_null) {
+            true => {
+                let message = expand_to_string(word, registry)?;
+                let message = if message.is_empty() { "parameter null or not set".to_string() } else { message };
+                registry.set_fatal_error();
+                return Err(format!("{}: {}", parameter.name, message));
+            }
+            false => push_value(fields, parameter, value.unwrap(), registry),
+        },
+        ParameterOp::Substring { offset, length } => {
+            let value = value.unwrap_or(Expanded::Text(String::new()));
+            push_value(fields, parameter, substring(parameter, value, offset, length.as_ref(), registry)?, registry);
+        }
+        ParameterOp::RemovePrefix { pattern, longest } => {
+            let pattern = Pattern::new(&expand_pattern(pattern, registry)?);
+            let value = value.unwrap_or(Expanded::Text(String::new()));
+            push_value(fields, parameter, value.map(|text| remove_prefix(text, &pattern, *longest)), registry);
+        }
+        ParameterOp::RemoveSuffix { pattern, longest } => {
+            let pattern = Pattern::new(&expand_pattern(pattern, registry)?);
+            let value = value.unwrap_or(Expanded::Text(String::new()));
+            push_value(fields, parameter, value.map(|text| remove_suffix(text, &pattern, *longest)), registry);
+        }
+        ParameterOp::Replace { pattern, replacement, mode } => {
+            let pattern = Pattern::new(&expand_pattern(pattern, registry)?);
+            let replacement = expand_to_string(replacement, registry)?;
+            let value = value.unwrap_or(Expanded::Text(String::new()));
+            push_value(fields, parameter, value.map(|text| replace(text, &pattern, &replacement, *mode)), registry);
+        }
+        ParameterOp::Case { upper, all } => {
+            let value = value.unwrap_or(Expanded::Text(String::new()));
+            push_value(fields, parameter, value.map(|text| change_case(text, *upper, *all)), registry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Under `set -u`, expanding an unset parameter is an error unless an
+/// operator says what to do in that case. `$@`, `$*` and whole arrays are
+/// exempt.
+fn check_bound(parameter: &Parameter, value: &Option<Expanded>, registry: &CommandRegistry) -> Result<(), String> {
+    let handles_unset = matches!(
+        parameter.op,
+        Some(ParameterOp::Default { .. } | ParameterOp::Alternative { .. } | ParameterOp::Assign { .. } | ParameterOp::Error { .. })
+    );
+    if !registry.is_option_set("nounset") || handles_unset || parameter.name == "@" || parameter.name == "*" {
+        return Ok(());
+    }
+
+    let name = &parameter.name;
+    let unbound = match (parameter.form, &parameter.subscript) {
+        (_, Some(Subscript::All | Subscript::Star)) => false,
+        (ParameterForm::Value, _) => value.is_none(),
+        (ParameterForm::Length, None) => registry.get_variable(name).is_none(),
+        (ParameterForm::Length, Some(Subscript::Index(index))) => element(parameter, index, registry)?.is_none(),
+        _ => false,
+    };
+    if !unbound {
+        return Ok(());
+    }
+
+    let name = match &parameter.subscript {
+        Some(Subscript::Index(index)) => format!("{}[{}]", name, expand_to_string(index, registry)?),
+        _ if name.chars().all(|c| c.is_ascii_digit()) => format!("${}", name),
+        _ => name.clone(),
+    };
+    registry.set_fatal_error();
+    Err(format!("{}: unbound variable", name))
+}
+
+fn expand_operand(parameter: &Parameter, word: &Word, registry: &CommandRegistry, fields: &mut Fields) -> Result<(), String> {
+    if parameter.quoted {
+        fields.push_str("");
+    }
+    expand_parts(&word.parts, registry, fields, !parameter.quoted)
+}
+
+/// Quoted `$*` and `${name[*]}` join their elements with the first character
+/// of IFS; this happens after any operator has been applied to each element.
+/// Unquoted results go through field splitting.
+fn push_value(fields: &mut Fields, parameter: &Parameter, value: Expanded, registry: &CommandRegistry) {
+    let star = match parameter.subscript {
+        Some(Subscript::Star) => true,
+        None => parameter.name == "*",
+        _ => false,
+    };
+
+    match value {
+        Expanded::List(values) if parameter.quoted && star => {
+            let ifs = registry.get_variable("IFS").unwrap_or_else(|| DEFAULT_IFS.to_string());
+            let separator: String = ifs.chars().take(1).collect();
+            fields.push_str(&values.join(&separator));
+        }
+        value if parameter.quoted => fields.push(&value),
+        Expanded::Text(text) => fields.push_split(&text),
+        Expanded::List(values) => fields.push_split_list(&values),
+    }
+}
+
+/// The parameter's current value, or `None` when it is unset.
+fn parameter_value(parameter: &Parameter, registry: &CommandRegistry) -> Result<Option<Expanded>, String> {
+    let name = parameter.name.as_str();
+    let value = match (parameter.form, &parameter.subscript) {
+        (ParameterForm::Names, _) => {
+            let names = registry.get_variable_names().into_iter()
+                .filter(|variable| variable.starts_with(name))
+                .collect();
+            Some(Expanded::List(names))
+        }
+
+        (ParameterForm::Value, None) if name == "@" || name == "*" => {
+            let params = registry.get_positional_params();
+            (!params.is_empty()).then_some(Expanded::List(params))
+        }
+        (ParameterForm::Value, None) => registry.get_variable(name).map(Expanded::Text),
+        (ParameterForm::Value, Some(Subscript::All | Subscript::Star)) => {
+            let exists = registry.get_value(name).is_some() || name == "@" || name == "*";
+            exists.then(|| Expanded::List(registry.get_array_values(name)))
+        }
+        (ParameterForm::Value, Some(Subscript::Index(index))) => {
+            element(parameter, index, registry)?.map(Expanded::Text)
+        }
+
+        (ParameterForm::Length, None) if name == "@" || name == "*" => {
+            Some(Expanded::Text(registry.get_positional_params().len().to_string()))
+        }
+        (ParameterForm::Length, None) => {
+            Some(Expanded::Text(registry.get_variable(name).unwrap_or_default().chars().count().to_string()))
+        }
+        (ParameterForm::Length, Some(Subscript::All | Subscript::Star)) => {
+            Some(Expanded::Text(registry.get_array_values(name).len().to_string()))
+        }
+        (ParameterForm::Length, Some(Subscript::Index(index))) => {
+            let element = element(parameter, index, registry)?.unwrap_or_default();
+            Some(Expanded::Text(element.chars().count().to_string()))
+        }
+
+        (ParameterForm::Keys, _) => Some(Expanded::List(registry.get_array_keys(name))),
+    };
+
+    Ok(value)
+}
+
+fn element(parameter: &Parameter, index: &Word, registry: &CommandRegistry) -> Result<Option<String>, String> {
+    let subscript = expand_to_string(index, registry)?;
+    registry.get_array_element(&parameter.name, &subscript)
+}
+
+fn assign_parameter(parameter: &Parameter, value: &str, registry: &CommandRegistry) -> Result<(), String> {
+    match &parameter.subscript {
+        None if is_valid_name(&parameter.name) => registry.set_variable(&parameter.name, value),
+        Some(Subscript::Index(index)) => {
+            let subscript = expand_to_string(index, registry)?;
+            registry.set_array_element(&parameter.name, &subscript, value)
+        }
+        _ => Err(format!("${}: cannot assign in this way", parameter.name)),
+    }
+}
+
+/// Builds a glob pattern from a word, escaping the quoted parts so that they
+/// only match literally.
+fn expand_pattern(word: &Word, registry: &CommandRegistry) -> Result<String, String> {
+    let mut pattern = String::new();
+
+    for part in &word.parts {
+        match part {
+            WordPart::Literal(text) => pattern.push_str(text),
+            WordPart::Quoted(text) => pattern.push_str(&escape_pattern(text)),
+            WordPart::Parameter(parameter) => {
+                let mut fields = Fields::default();
+                expand_parameter(parameter, registry, &mut fields)?;
+                let text = fields.into_fields().join(" ");
+                pattern.push_str(&if parameter.quoted { escape_pattern(&text) } else { text });
+            }
+            WordPart::Command { source, quoted } => {
+                let output = ShellExecutor::new(registry).capture_output(source)?;
+                pattern.push_str(&if *quoted { escape_pattern(&output) } else { output });
+            }
+        }
+    }
+
+    Ok(pattern)
+}
+
+fn substring(
+    parameter: &Parameter,
+    value: Expanded,
+    offset: &Word,
+    length: Option<&Word>,
+    registry: &CommandRegistry,
+) -> Result<Expanded, String> {
+    let evaluate = |word: &Word| -> Result<i64, String> {
+        let expression = expand_to_string(word, registry)?;
+        registry.evaluate_integer(&expression).ok_or_else(|| format!("{}: bad substitution", expression.trim()))
+    };
+    let offset = evaluate(offset)?;
+    let length = length.map(evaluate).transpose()?;
+
+    match value {
+        Expanded::Text(text) => {
+            let chars: Vec<char> = text.chars().collect();
+            let (start, end) = slice_bounds(chars.len(), offset, length)
+                .ok_or_else(|| format!("{}: substring expression < 0", length.unwrap_or_default()))?;
+            Ok(Expanded::Text(chars[start..end].iter().collect()))
+        }
+        Expanded::List(mut values) => {
+            if length.is_some_and(|length| length < 0) {
+                return Err(format!("{}: substring expression < 0", length.unwrap_or_default()));
+            }
+            // `${@:n}` counts from `$0`, so offset 1 starts at the first parameter.
+            if parameter.subscript.is_none() {
+                values.insert(0, registry.get_variable("0").unwrap_or_default());
+            }
+            let (start, end) = slice_bounds(values.len(), offset, length).unwrap_or((0, 0));
+            Ok(Expanded::List(values[start..end].to_vec()))
+        }
+    }
+}
+
+/// Turns an offset and optional length (negative values count from the end)
+/// into a range, or `None` when the length points before the offset.
+fn slice_bounds(len: usize, offset: i64, length: Option<i64>) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if offset < 0 { len + offset } else { offset };
+    if start < 0 || start > len {
+        return Some((0, 0));
+    }
+
+    let end = match length {
+        None => len,
+        Some(length) if length < 0 => len + length,
+        Some(length) => (start + length).min(len),
+    };
+    if end < start {
+        return None;
+    }
+
+    Some((start as usize, end as usize))
+}
+
+fn remove_prefix(text: &str, pattern: &Pattern, longest: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut ends: Box<dyn Iterator<Item = usize>> = if longest {
+        Box::new((0..=chars.len()).rev())
+    } else {
+        Box::new(0..=chars.len())
+    };
+
+    match ends.find(|&end| pattern.matches(&chars[..end])) {
+        Some(end) => chars[end..].iter().collect(),
+        None => text.to_string(),
+    }
+}
+
+fn remove_suffix(text: &str, pattern: &Pattern, longest: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut starts: Box<dyn Iterator<Item = usize>> = if longest {
+        Box::new(0..=chars.len())
+    } else {
+        Box::new((0..=chars.len()).rev())
+    };
+
+    match starts.find(|&start| pattern.matches(&chars[start..])) {
+        Some(start) => chars[..start].iter().collect(),
+        None => text.to_string(),
+    }
+}
+
+/// `${var/pat/rep}` and friends, always replacing the longest match.
+fn replace(text: &str, pattern: &Pattern, replacement: &str, mode: ReplaceMode) -> String {
+    // An empty pattern anchors `/#` and `/%` but matches nothing otherwise.
+    if pattern.is_empty() && matches!(mode, ReplaceMode::First | ReplaceMode::All) {
+        return text.to_string();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let len = chars.len();
+
+    match mode {
+        ReplaceMode::Prefix => match (0..=len).rev().find(|&end| pattern.matches(&chars[..end])) {
+            Some(end) => format!("{}{}", replacement, chars[end..].iter().collect::<String>()),
+            None => text.to_string(),
+        },
+        ReplaceMode::Suffix => match (0..=len).find(|&start| pattern.matches(&chars[start..])) {
+            Some(start) => format!("{}{}", chars[..start].iter().collect::<String>(), replacement),
+            None => text.to_string(),
+        },
+        ReplaceMode::First | ReplaceMode::All => {
+            let mut result = String::new();
+            let mut i = 0;
+
+            while i < len {
+                match (i + 1..=len).rev().find(|&end| pattern.matches(&chars[i..end])) {
+                    Some(end) => {
+                        result.push_str(replacement);
+                        i = end;
+                        if mode == ReplaceMode::First {
+                            result.extend(&chars[i..]);
+                            return result;
+                        }
+                    }
+                    None => {
+                        result.push(chars[i]);
+                        i += 1;
+                    }
+                }
+            }
+
+            result
+        }
+    }
+}
+
+fn change_case(text: &str, upper: bool, all: bool) -> String {
+    let convert = |c: char| -> String {
+        if upper { c.to_uppercase().collect() } else { c.to_lowercase().collect() }
+    };
+
+    if all {
+        return text.chars().map(convert).collect();
+    }
+
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => convert(first) + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(ifs: &str, text: &str) -> Vec<String> {
+        let mut fields = Fields { ifs: ifs.to_string(), ..Fields::default() };
+        fields.push_split(text);
+        fields.into_fields()
+    }
+
+    #[test]
+    fn whitespace_runs_separate_fields() {
+        assert_eq!(split(DEFAULT_IFS, "  a \t b\n"), ["a", "b"]);
+        assert!(split(DEFAULT_IFS, "   ").is_empty());
+        assert_eq!(split("", " a b "), [" a b "]);
+    }
+
+    #[test]
+    fn other_ifs_characters_end_empty_fields() {
+        assert_eq!(split(":", "a::b:"), ["a", "", "b"]);
+        assert_eq!(split(" :", " a : b "), ["a", "b"]);
+        assert_eq!(split(" :", "a :: b"), ["a", "", "b"]);
+    }
+
+    #[test]
+    fn slice_bounds_count_negative_values_from_the_end() {
+        assert_eq!(slice_bounds(5, 1, Some(3)), Some((1, 4)));
+        assert_eq!(slice_bounds(5, -2, None), Some((3, 5)));
+        assert_eq!(slice_bounds(5, 1, Some(-1)), Some((1, 4)));
+        assert_eq!(slice_bounds(5, 2, Some(10)), Some((2, 5)));
+        assert_eq!(slice_bounds(5, 9, None), Some((0, 0)));
+        assert_eq!(slice_bounds(5, 3, Some(-4)), None);
+    }
+
+    #[test]
+    fn shortest_and_longest_removal() {
+        let pattern = Pattern::new("*.");
+        assert_eq!(remove_prefix("a.b.c", &pattern, false), "b.c");
+        assert_eq!(remove_prefix("a.b.c", &pattern, true), "c");
+        let pattern = Pattern::new(".*");
+        assert_eq!(remove_suffix("a.b.c", &pattern, false), "a.b");
+        assert_eq!(remove_suffix("a.b.c", &pattern, true), "a");
+        assert_eq!(remove_suffix("abc", &pattern, true), "abc");
+    }
+
+    #[test]
+    fn replace_modes() {
+        let pattern = Pattern::new("o*");
+        assert_eq!(replace("foo bar", &Pattern::new("o"), "0", ReplaceMode::First), "f0o bar");
+        assert_eq!(replace("foo bar", &Pattern::new("o"), "0", ReplaceMode::All), "f00 bar");
+        assert_eq!(replace("foo bar", &pattern, "", ReplaceMode::First), "f");
+        assert_eq!(replace("foo", &Pattern::new("f"), "b", ReplaceMode::Prefix), "boo");
+        assert_eq!(replace("foo", &Pattern::new("f"), "b", ReplaceMode::Suffix), "foo");
+        assert_eq!(replace("foo", &Pattern::new(""), "x", ReplaceMode::All), "foo");
+        assert_eq!(replace("foo", &Pattern::new(""), "pre", ReplaceMode::Prefix), "prefoo");
+        assert_eq!(replace("foo", &Pattern::new(""), "suf", ReplaceMode::Suffix), "foosuf");
+    }
+
+    #[test]
+    fn case_changes() {
+        assert_eq!(change_case("hello", true, false), "Hello");
+        assert_eq!(change_case("hello", true, true), "HELLO");
+        assert_eq!(change_case("HeLLo", false, true), "hello");
+        assert_eq!(change_case("", true, false), "");
+    }
+}
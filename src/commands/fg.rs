@@ -0,0 +1,43 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::jobs::JobState;
+
+pub struct FgCommand;
+
+impl Command for FgCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        if !registry.has_job_control() {
+            return Err("fg: no job control".to_string());
+        }
+
+        let spec = args.first().map(String::as_str).unwrap_or("%+");
+        let mut job = {
+            let mut jobs = registry.jobs();
+            let id = jobs.resolve(spec).map_err(|e| format!("fg: {}", e))?;
+            jobs.remove(id).ok_or_else(|| format!("fg: {}: no such job", spec))?
+        };
+
+        writeln!(output, "{}", job.command).map_err(|e| e.to_string())?;
+        output.flush().map_err(|e| e.to_string())?;
+
+        job.wait_in_foreground(true, true);
+
+        if let Some(message) = job.termination_message() {
+            writeln!(error, "{}", message).map_err(|e| e.to_string())?;
+        }
+
+        match job.state() {
+            JobState::Done(code) => Ok(ShellStatus::Status(code)),
+            _ => {
+                let report = registry.jobs().add_stopped(job);
+                writeln!(error, "\n{}", report).map_err(|e| e.to_string())?;
+                Ok(ShellStatus::Status(128 + libc::SIGTSTP))
+            }
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "fg"
+    }
+}
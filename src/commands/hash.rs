@@ -0,0 +1,123 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+pub struct HashCommand;
+
+impl Command for HashCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut reset = false;
+        let mut show_paths = false;
+        let mut path = None;
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for (index, flag) in flags.char_indices().skip(1) {
+                match flag {
+                    'r' => reset = true,
+                    't' => show_paths = true,
+                    'p' => {
+                        let rest = &flags[index + 1..];
+                        path = if rest.is_empty() { iter.next().cloned() } else { Some(rest.to_string()) };
+                        if path.is_none() {
+                            return usage_error(error, "-p: option requires an argument");
+                        }
+                        break;
+                    }
+                    _ => return usage_error(error, &format!("-{}: invalid option", flag)),
+                }
+            }
+        }
+        let names: Vec<&String> = iter.collect();
+
+        // Hashing a path would get around a restricted shell's `PATH`.
+        if let Some(path) = &path
+            && path.contains('/')
+            && registry.is_option_set("restricted") {
+            writeln!(error, "hash: {}: restricted", path).map_err(|e| e.to_string())?;
+            return Ok(ShellStatus::Status(1));
+        }
+
+        if reset {
+            registry.command_cache().clear();
+        }
+
+        if show_paths {
+            if names.is_empty() {
+                writeln!(error, "hash: -t: option requires an argument").map_err(|e| e.to_string())?;
+                return Ok(ShellStatus::Status(1));
+            }
+            return print_paths(&names, registry, output, error);
+        }
+
+        if names.is_empty() {
+            if reset || path.is_some() {
+                return Ok(ShellStatus::Continue);
+            }
+            return print_table(registry, output);
+        }
+
+        let mut status = 0;
+        for name in names {
+            if let Some(path) = &path {
+                registry.command_cache().insert(name, path);
+            } else if name.contains('/') || registry.get_builtin(name).is_some() {
+                continue;
+            } else if let Some(path) = registry.get_executable_path(name) {
+                registry.command_cache().insert(name, &path);
+            } else {
+                writeln!(error, "hash: {}: not found", name).map_err(|e| e.to_string())?;
+                status = 1;
+            }
+        }
+        Ok(ShellStatus::Status(status))
+    }
+
+    fn get_name(&self) -> &str {
+        "hash"
+    }
+}
+
+fn usage_error(error: &mut dyn Write, message: &str) -> Result<ShellStatus, String> {
+    writeln!(error, "hash: {}", message).map_err(|e| e.to_string())?;
+    writeln!(error, "hash: usage: hash [-r] [-p pathname] [-t] [name ...]").map_err(|e| e.to_string())?;
+    Ok(ShellStatus::Status(2))
+}
+
+fn print_table(registry: &CommandRegistry, output: &mut dyn Write) -> Result<ShellStatus, String> {
+    let cache = registry.command_cache();
+    let hashed = cache.hashed();
+    if hashed.is_empty() {
+        writeln!(output, "hash: hash table empty").map_err(|e| e.to_string())?;
+        return Ok(ShellStatus::Continue);
+    }
+
+    writeln!(output, "hits\tcommand").map_err(|e| e.to_string())?;
+    for (_, path, hits) in hashed {
+        writeln!(output, "{:4}\t{}", hits, path).map_err(|e| e.to_string())?;
+    }
+    Ok(ShellStatus::Continue)
+}
+
+/// `hash -t` prints the remembered path, prefixed by the name when several
+/// are asked for.
+fn print_paths(names: &[&String], registry: &CommandRegistry, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+    let cache = registry.command_cache();
+    let hashed = cache.hashed();
+
+    let mut status = 0;
+    for name in names {
+        match hashed.iter().find(|(hashed_name, _, _)| hashed_name == name) {
+            Some((_, path, _)) if names.len() > 1 => writeln!(output, "{}\t{}", name, path),
+            Some((_, path, _)) => writeln!(output, "{}", path),
+            None => {
+                status = 1;
+                writeln!(error, "hash: {}: not found", name)
+            }
+        }.map_err(|e| e.to_string())?;
+    }
+    Ok(ShellStatus::Status(status))
+}
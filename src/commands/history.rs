@@ -1,10 +1,10 @@
 use super::{Command, ShellStatus, CommandRegistry};
-use std::io::Write;
+use std::io::{Read, Write};
 
 pub struct HistoryCommand;
 
 impl Command for HistoryCommand {
-    fn execute(&self, args: &[String], registry: &CommandRegistry, output: &mut dyn Write) -> Result<ShellStatus, String> {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
         match args.first().map(|s| s.as_str()) {
             Some("-w") => {
                 let path = args.get(1).ok_or("history: -w: argument required")?;
@@ -0,0 +1,394 @@
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::OnceLock;
+use super::executor::status_code;
+use super::signals::{ignoring_interrupts, take_interrupt};
+use super::times::ChildTimes;
+
+/// Terminal modes of the shell itself, restored whenever a foreground job
+/// stops or exits so a program that crashed in raw mode cannot break the
+/// prompt.
+static SHELL_TMODES: OnceLock<libc::termios> = OnceLock::new();
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum JobState {
+    Running,
+    Stopped,
+    Done(i32),
+}
+
+pub struct Process {
+    pub pid: i32,
+    status: Option<i32>,
+    signal: Option<i32>,
+    core_dumped: bool,
+    stopped: bool,
+    times: ChildTimes,
+}
+
+impl Process {
+    /// Records a status reported by `wait4`, along with the CPU time the
+    /// process used once it has exited.
+    fn update(&mut self, raw: i32, usage: &libc::rusage) {
+        if libc::WIFSTOPPED(raw) {
+            self.stopped = true;
+        } else if libc::WIFCONTINUED(raw) {
+            self.stopped = false;
+        } else {
+            let status = ExitStatus::from_raw(raw);
+            self.stopped = false;
+            self.status = Some(status_code(status));
+            self.signal = status.signal();
+            self.core_dumped = status.core_dumped();
+            self.times = ChildTimes::from_rusage(usage);
+        }
+    }
+}
+
+/// A pipeline running in its own process group.
+pub struct Job {
+    pub id: usize,
+    pub pgid: i32,
+    pub processes: Vec<Process>,
+    pub command: String,
+    tmodes: Option<libc::termios>,
+}
+
+impl Job {
+    pub fn new(pgid: i32, pids: &[i32], command: String) -> Self {
+        let processes = pids.iter().map(|&pid| Process { pid, status: None, signal: None, core_dumped: false, stopped: false, times: ChildTimes::default() }).collect();
+        Job { id: 0, pgid, processes, command, tmodes: None }
+    }
+
+    /// Done once every process has exited, with the status of the last one.
+    pub fn state(&self) -> JobState {
+        if self.processes.iter().all(|process| process.status.is_some()) {
+            return JobState::Done(self.processes.last().and_then(|process| process.status).unwrap_or(0));
+        }
+        if self.processes.iter().any(|process| process.stopped) {
+            return JobState::Stopped;
+        }
+        JobState::Running
+    }
+
+    /// The `[1]+  Done                    sleep 5` line used by `jobs` and
+    /// job reports; `show_pid` adds the process group id as `jobs -l` does.
+    pub fn describe(&self, marker: char, show_pid: bool) -> String {
+        let state = match (self.state(), self.signal()) {
+            (JobState::Running, _) => "Running".to_string(),
+            (JobState::Stopped, _) => "Stopped".to_string(),
+            (JobState::Done(_), Some(signal)) => signal_description(signal),
+            (JobState::Done(0), None) => "Done".to_string(),
+            (JobState::Done(code), None) => format!("Exit {}", code),
+        };
+        let pid = if show_pid { format!(" {}", self.pgid) } else { String::new() };
+        let suffix = if self.state() == JobState::Running { " &" } else { "" };
+        format!("[{}]{}{}  {:<24}{}{}", self.id, marker, pid, state, self.command, suffix)
+    }
+
+    /// Polls the job's processes without blocking.
+    fn poll(&mut self) {
+        for process in self.processes.iter_mut().filter(|process| process.status.is_none()) {
+            let mut raw = 0;
+            let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+            let flags = libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED;
+            match unsafe { libc::wait4(process.pid, &mut raw, flags, &mut usage) } {
+                0 => {}
+                pid if pid == process.pid => process.update(raw, &usage),
+                _ => process.status = Some(0),
+            }
+        }
+    }
+
+    /// The `$?`-style status of each process, in pipeline order; stopped
+    /// processes count as `128+SIGTSTP`.
+    pub fn statuses(&self) -> Vec<i32> {
+        self.processes.iter()
+            .map(|process| match process.status {
+                Some(status) if !process.stopped => status,
+                _ if process.stopped => 128 + libc::SIGTSTP,
+                _ => 0,
+            })
+            .collect()
+    }
+
+    /// CPU time used by the processes that have exited so far.
+    pub fn child_times(&self) -> ChildTimes {
+        let mut times = ChildTimes::default();
+        for process in &self.processes {
+            times += process.times;
+        }
+        times
+    }
+
+    /// The last process's fatal signal, if it was killed by one.
+    pub fn signal(&self) -> Option<i32> {
+        self.processes.last().and_then(|process| process.signal)
+    }
+
+    /// What to print when a foreground job is killed by a signal: just a
+    /// newline after Ctrl-C, nothing for SIGPIPE, otherwise the signal's
+    /// description (`Killed`, `Segmentation fault (core dumped)`).
+    pub fn termination_message(&self) -> Option<String> {
+        let process = self.processes.last()?;
+        match process.signal? {
+            libc::SIGPIPE => None,
+            libc::SIGINT => Some(String::new()),
+            signal if process.core_dumped => Some(format!("{} (core dumped)", signal_description(signal))),
+            signal => Some(signal_description(signal)),
+        }
+    }
+
+    /// Blocks until every process has exited or one of them stops. An
+    /// `interruptible` wait (the `wait` builtin) gives up early, leaving the
+    /// job running, when Ctrl-C or a trapped signal arrives, and returns
+    /// that signal.
+    pub fn wait(&mut self, interruptible: bool) -> Option<i32> {
+        if interruptible {
+            take_interrupt();
+        }
+
+        for process in self.processes.iter_mut().filter(|process| process.status.is_none()) {
+            loop {
+                let mut raw = 0;
+                let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+                let result = unsafe { libc::wait4(process.pid, &mut raw, libc::WUNTRACED, &mut usage) };
+                if result < 0 {
+                    if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                        if interruptible && let Some(signal) = take_interrupt() {
+                            return Some(signal);
+                        }
+                        continue;
+                    }
+                    process.status = Some(0);
+                    break;
+                }
+
+                process.update(raw, &usage);
+                if process.stopped {
+                    return None;
+                }
+                break;
+            }
+        }
+        None
+    }
+
+    /// Hands the terminal to the job (resuming it if `resume`), waits until it
+    /// exits or stops and takes the terminal back.
+    pub fn wait_in_foreground(&mut self, job_control: bool, resume: bool) {
+        if job_control {
+            unsafe {
+                libc::tcsetpgrp(0, self.pgid);
+                if let Some(tmodes) = &self.tmodes {
+                    libc::tcsetattr(0, libc::TCSADRAIN, tmodes);
+                }
+            }
+        }
+        if resume {
+            self.resume();
+        }
+
+        ignoring_interrupts(|| self.wait(false));
+
+        if job_control {
+            unsafe {
+                if self.state() == JobState::Stopped {
+                    let mut tmodes: libc::termios = std::mem::zeroed();
+                    if libc::tcgetattr(0, &mut tmodes) == 0 {
+                        self.tmodes = Some(tmodes);
+                    }
+                }
+                libc::tcsetpgrp(0, libc::getpgrp());
+                if let Some(tmodes) = SHELL_TMODES.get() {
+                    libc::tcsetattr(0, libc::TCSADRAIN, tmodes);
+                }
+            }
+        }
+    }
+
+    /// Sends `SIGCONT` to the whole process group.
+    pub fn resume(&mut self) {
+        for process in &mut self.processes {
+            process.stopped = false;
+        }
+        unsafe { libc::kill(-self.pgid, libc::SIGCONT) };
+    }
+}
+
+/// Jobs started with `&` or stopped with Ctrl-Z. Job numbers grow from 1 and
+/// are only reused once the jobs holding them are gone. The most recently
+/// started, stopped or resumed job is the current one (`%+`), the one before
+/// it the previous one (`%-`).
+#[derive(Default)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+    recency: Vec<usize>,
+}
+
+impl JobTable {
+    /// Adds a new job, giving it the next free job number.
+    pub fn add(&mut self, mut job: Job) -> usize {
+        job.id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        self.insert(job)
+    }
+
+    /// Puts back a job taken out with `remove`, keeping its number.
+    pub fn insert(&mut self, job: Job) -> usize {
+        let id = job.id;
+        let position = self.jobs.iter().position(|other| other.id > id).unwrap_or(self.jobs.len());
+        self.jobs.insert(position, job);
+        self.touch(id);
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        let position = self.jobs.iter().position(|job| job.id == id)?;
+        self.recency.retain(|&other| other != id);
+        Some(self.jobs.remove(position))
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn ids(&self) -> Vec<usize> {
+        self.jobs.iter().map(|job| job.id).collect()
+    }
+
+    /// Makes `id` the current job.
+    pub fn touch(&mut self, id: usize) {
+        self.recency.retain(|&other| other != id);
+        self.recency.push(id);
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    /// `+` for the current job, `-` for the previous one.
+    pub fn marker(&self, id: usize) -> char {
+        match self.recency.iter().rev().position(|&other| other == id) {
+            Some(0) => '+',
+            Some(1) => '-',
+            _ => ' ',
+        }
+    }
+
+    pub fn reap(&mut self) {
+        for job in &mut self.jobs {
+            job.poll();
+        }
+    }
+
+    /// Removes finished jobs from the table, returning their report lines.
+    pub fn take_finished(&mut self) -> Vec<String> {
+        self.reap();
+        let reports = self.jobs.iter()
+            .filter(|job| matches!(job.state(), JobState::Done(_)))
+            .map(|job| job.describe(self.marker(job.id), false))
+            .collect();
+        self.remove_finished();
+        reports
+    }
+
+    pub fn remove_finished(&mut self) {
+        for id in self.ids() {
+            if self.get(id).is_some_and(|job| matches!(job.state(), JobState::Done(_))) {
+                self.remove(id);
+            }
+        }
+    }
+
+    /// Files a job that stopped in the foreground, making it the current
+    /// job, and returns the line reporting it.
+    pub fn add_stopped(&mut self, job: Job) -> String {
+        let id = if job.id == 0 { self.add(job) } else { self.insert(job) };
+        self.get(id).map(|job| job.describe('+', false)).unwrap_or_default()
+    }
+
+    /// Resolves a job specification: `%+` / `%%` / `%` (current), `%-`
+    /// (previous), `%n` or `n` (job number), `%string` (command starts with
+    /// `string`) and `%?string` (command contains `string`).
+    pub fn resolve(&self, spec: &str) -> Result<usize, String> {
+        let no_such_job = || format!("{}: no such job", spec);
+        let body = spec.strip_prefix('%').unwrap_or(spec);
+
+        match body {
+            "" | "%" | "+" => self.recency.last().copied().ok_or_else(|| "current: no such job".to_string()),
+            "-" => {
+                let index = self.recency.len().checked_sub(2);
+                index.map(|index| self.recency[index]).ok_or_else(no_such_job)
+            }
+            number if number.chars().all(|c| c.is_ascii_digit()) => {
+                let id: usize = number.parse().map_err(|_| no_such_job())?;
+                self.jobs.iter().any(|job| job.id == id).then_some(id).ok_or_else(no_such_job)
+            }
+            _ if !spec.starts_with('%') => Err(no_such_job()),
+            text => {
+                let matches: Vec<usize> = match text.strip_prefix('?') {
+                    Some(needle) => self.jobs.iter().filter(|job| job.command.contains(needle)).map(|job| job.id).collect(),
+                    None => self.jobs.iter().filter(|job| job.command.starts_with(text)).map(|job| job.id).collect(),
+                };
+                match matches.as_slice() {
+                    [id] => Ok(*id),
+                    [] => Err(no_such_job()),
+                    _ => Err(format!("{}: ambiguous job spec", spec)),
+                }
+            }
+        }
+    }
+
+    /// The job containing process `pid`, if any.
+    pub fn find_pid(&self, pid: i32) -> Option<usize> {
+        self.jobs.iter()
+            .find(|job| job.pgid == pid || job.processes.iter().any(|process| process.pid == pid))
+            .map(|job| job.id)
+    }
+}
+
+/// The description of a signal (`Killed`, `Terminated`, ...).
+fn signal_description(signal: i32) -> String {
+    let description = unsafe { libc::strsignal(signal) };
+    if description.is_null() {
+        return format!("Signal {}", signal);
+    }
+    unsafe { std::ffi::CStr::from_ptr(description) }.to_string_lossy().into_owned()
+}
+
+/// Puts an interactive shell in its own process group in the foreground of
+/// the terminal, ignoring the job control signals that would otherwise stop
+/// it. Returns false when stdin is not a terminal we can control.
+pub fn init_job_control() -> bool {
+    unsafe {
+        if libc::isatty(0) == 0 {
+            return false;
+        }
+
+        // Wait until we are in the foreground before taking over the terminal.
+        loop {
+            let pgrp = libc::getpgrp();
+            if libc::tcgetpgrp(0) == pgrp {
+                break;
+            }
+            libc::kill(-pgrp, libc::SIGTTIN);
+        }
+
+        for signal in [libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU] {
+            libc::signal(signal, libc::SIG_IGN);
+        }
+
+        let pid = libc::getpid();
+        if libc::getpgrp() != pid && libc::setpgid(pid, pid) < 0 {
+            return false;
+        }
+        libc::tcsetpgrp(0, pid);
+
+        let mut tmodes: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(0, &mut tmodes) == 0 {
+            let _ = SHELL_TMODES.set(tmodes);
+        }
+    }
+    true
+}
@@ -0,0 +1,55 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::jobs::JobState;
+
+pub struct JobsCommand;
+
+impl Command for JobsCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut show_pid = false;
+        let mut pids_only = false;
+        let mut running_only = false;
+        let mut stopped_only = false;
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    'l' => show_pid = true,
+                    'p' => pids_only = true,
+                    'r' => running_only = true,
+                    's' => stopped_only = true,
+                    _ => return Err(format!("jobs: -{}: invalid option", flag)),
+                }
+            }
+        }
+
+        let mut jobs = registry.jobs();
+        jobs.reap();
+
+        let ids = match iter.peek() {
+            None => jobs.ids(),
+            Some(_) => iter.map(|spec| jobs.resolve(spec).map_err(|e| format!("jobs: {}", e)))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        for id in ids {
+            let Some(job) = jobs.get(id) else { continue };
+            let state = job.state();
+            if (running_only && state != JobState::Running) || (stopped_only && state != JobState::Stopped) {
+                continue;
+            }
+
+            let line = if pids_only { job.pgid.to_string() } else { job.describe(jobs.marker(id), show_pid) };
+            writeln!(output, "{}", line).map_err(|e| e.to_string())?;
+        }
+
+        jobs.remove_finished();
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "jobs"
+    }
+}
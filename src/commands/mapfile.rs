@@ -0,0 +1,50 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use crate::utils::{is_valid_name, read_until};
+
+/// `mapfile` / `readarray`: reads every line of input into an indexed array.
+pub struct MapfileCommand {
+    name: &'static str,
+}
+
+impl MapfileCommand {
+    pub fn new(name: &'static str) -> Self {
+        Self { name }
+    }
+}
+
+impl Command for MapfileCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, input: &mut dyn Read, _: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut trim = false;
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    't' => trim = true,
+                    _ => return Err(format!("{}: -{}: invalid option", self.name, flag)),
+                }
+            }
+        }
+
+        let array = iter.next().map(String::as_str).unwrap_or("MAPFILE");
+        if !is_valid_name(array) {
+            return Err(format!("{}: `{}': not a valid identifier", self.name, array));
+        }
+
+        let mut lines = Vec::new();
+        while let Some((mut line, complete)) = read_until(input, b'\n') {
+            if complete && !trim {
+                line.push('\n');
+            }
+            lines.push((None, line));
+        }
+
+        registry.set_array(array, lines)?;
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        self.name
+    }
+}
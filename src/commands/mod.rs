@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::io::{Read, Write};
 
 mod echo;
 mod exit;
@@ -8,18 +8,54 @@ mod pwd;
 mod cd;
 mod executor;
 mod history;
+mod source;
+mod return_cmd;
+mod declare;
+mod variables;
+mod expansion;
+mod jobs;
+mod signals;
+mod jobs_cmd;
+mod fg;
+mod bg;
+mod disown;
+mod wait;
+mod trap;
+mod read;
+mod mapfile;
+mod set;
+mod command_cache;
+mod hash;
+mod exec;
+mod times;
+mod ulimit;
+mod umask;
+mod shopt;
+mod command;
+mod builtin;
 
 pub use registry::CommandRegistry;
 pub use executor::ShellExecutor;
+pub use jobs::init_job_control;
+pub use signals::init_interactive_signals;
 
 pub enum ShellStatus {
     Continue,
-    Exit,
+    Status(i32),
+    Return(i32),
+    Exit(i32),
+}
+
+impl ShellStatus {
+    /// `return` and `exit` unwind out of the script or file being run.
+    pub fn stops_execution(&self) -> bool {
+        matches!(self, ShellStatus::Return(_) | ShellStatus::Exit(_))
+    }
 }
 
 pub trait Command {
     fn execute(&self, args: &[String], registry: &CommandRegistry,
-         output: &mut dyn Write) -> Result<ShellStatus, String>;
+         input: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String>;
     fn get_name(&self) -> &str;
     fn get_type(&self) -> &str {
         "shell builtin"
@@ -0,0 +1,138 @@
+use std::io::{self, IsTerminal, Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use crate::utils::{is_valid_name, read_until};
+
+const DEFAULT_IFS: &str = " \t\n";
+
+pub struct ReadCommand;
+
+impl Command for ReadCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, input: &mut dyn Read, _: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut raw = false;
+        let mut prompt = None;
+        let mut array = None;
+        let mut delimiter = b'\n';
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for (index, flag) in flags.char_indices().skip(1) {
+                match flag {
+                    'r' => raw = true,
+                    'p' | 'a' | 'd' => {
+                        // The option's value is the rest of this word or the next one.
+                        let rest = &flags[index + 1..];
+                        let value = if rest.is_empty() {
+                            iter.next().cloned().ok_or_else(|| format!("read: -{}: option requires an argument", flag))?
+                        } else {
+                            rest.to_string()
+                        };
+                        match flag {
+                            'p' => prompt = Some(value),
+                            'a' => array = Some(value),
+                            _ => delimiter = value.bytes().next().unwrap_or(0),
+                        }
+                        break;
+                    }
+                    _ => return Err(format!("read: -{}: invalid option", flag)),
+                }
+            }
+        }
+
+        let names: Vec<&String> = iter.collect();
+        if let Some(name) = names.iter().chain(array.as_ref().iter()).find(|name| !is_valid_name(name)) {
+            return Err(format!("read: `{}': not a valid identifier", name));
+        }
+
+        if let Some(prompt) = prompt
+            && io::stdin().is_terminal() {
+            write!(error, "{}", prompt).and_then(|_| error.flush()).map_err(|e| e.to_string())?;
+        }
+
+        let (line, complete) = read_line(input, delimiter, raw);
+        let ifs = registry.get_variable("IFS").unwrap_or_else(|| DEFAULT_IFS.to_string());
+
+        if let Some(array) = array {
+            let fields = split_fields(&line, &ifs, usize::MAX);
+            registry.set_array(&array, fields.into_iter().map(|field| (None, field)).collect())?;
+        } else if names.is_empty() {
+            registry.set_variable("REPLY", &line.iter().map(|(c, _)| c).collect::<String>())?;
+        } else {
+            let mut fields = split_fields(&line, &ifs, names.len()).into_iter();
+            for name in names {
+                registry.set_variable(name, &fields.next().unwrap_or_default())?;
+            }
+        }
+
+        Ok(ShellStatus::Status(if complete { 0 } else { 1 }))
+    }
+
+    fn get_name(&self) -> &str {
+        "read"
+    }
+}
+
+/// Reads one line as characters marked with whether a backslash escaped
+/// them. Without `-r`, a backslash-newline continues the line.
+fn read_line(input: &mut dyn Read, delimiter: u8, raw: bool) -> (Vec<(char, bool)>, bool) {
+    let mut line = Vec::new();
+
+    loop {
+        let Some((text, complete)) = read_until(input, delimiter) else {
+            return (line, false);
+        };
+
+        let mut chars = text.chars();
+        let mut continued = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if !raw => match chars.next() {
+                    Some(escaped) => line.push((escaped, true)),
+                    None => continued = complete,
+                },
+                _ => line.push((c, false)),
+            }
+        }
+
+        if !continued {
+            return (line, complete);
+        }
+    }
+}
+
+/// Splits a line on `IFS` into at most `limit` fields; the last one keeps
+/// the rest of the line, minus trailing `IFS` whitespace.
+fn split_fields(line: &[(char, bool)], ifs: &str, limit: usize) -> Vec<String> {
+    let is_separator = |(c, escaped): &(char, bool)| !escaped && ifs.contains(*c);
+    let is_blank = |entry: &(char, bool)| is_separator(entry) && entry.0.is_whitespace();
+
+    let mut fields = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.iter().position(|entry| !is_blank(entry)) {
+        rest = &rest[start..];
+
+        if fields.len() + 1 == limit {
+            let end = rest.iter().rposition(|entry| !is_blank(entry)).map_or(0, |end| end + 1);
+            fields.push(rest[..end].iter().map(|(c, _)| c).collect());
+            break;
+        }
+
+        let end = rest.iter().position(is_separator).unwrap_or(rest.len());
+        fields.push(rest[..end].iter().map(|(c, _)| c).collect());
+        rest = &rest[end..];
+
+        // One non-whitespace separator (with the blanks around it) ends a field.
+        let blanks = rest.iter().take_while(|entry| is_blank(entry)).count();
+        rest = &rest[blanks..];
+        if rest.first().is_some_and(is_separator) {
+            rest = &rest[1..];
+        }
+        if rest.is_empty() {
+            break;
+        }
+    }
+    fields
+}
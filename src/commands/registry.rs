@@ -1,40 +1,71 @@
 use super::{Command}; 
 use super::{echo::EchoCommand, exit::ExitCommand, type_cmd::TypeCommand, pwd::PwdCommand, cd::CdCommand, history::HistoryCommand};
+use super::{source::SourceCommand, return_cmd::ReturnCommand, declare::DeclareCommand};
+use super::{jobs_cmd::JobsCommand, fg::FgCommand, bg::BgCommand, disown::DisownCommand, wait::WaitCommand};
+use super::{trap::TrapCommand, read::ReadCommand, mapfile::MapfileCommand, set::SetCommand, hash::HashCommand};
+use super::exec::ExecCommand;
+use super::times::TimesCommand;
+use super::{ulimit::UlimitCommand, umask::UmaskCommand, shopt::ShoptCommand};
+use super::{command::CommandCommand, builtin::BuiltinCommand};
+use super::command_cache::{CommandCache, is_executable};
+use super::variables::{OPTION_FLAGS, SHELL_OPTIONS, SHOPT_OPTIONS, ShellParameters, Value, Variable};
+use super::jobs::JobTable;
+use super::signals::Traps;
 use std::io::Write;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{RefCell, RefMut};
+use std::rc::Rc;
+use std::collections::{BTreeMap, HashMap};
 use std::os::unix::fs::PermissionsExt;
 use std::{env, fs};
-use crate::utils::open_file;
+use crate::utils::{Pipeline, open_file};
 
 pub struct CommandRegistry {
     pub builtins: HashMap<String, Box<dyn Command>>,
-    pub executables: HashMap<String, String>,
 
     history: RefCell<Vec<String>>,
+    variables: RefCell<HashMap<String, Variable>>,
+    parameters: RefCell<ShellParameters>,
+    jobs: RefCell<JobTable>,
+    traps: RefCell<Traps>,
+    command_cache: RefCell<CommandCache>,
+    functions: RefCell<HashMap<String, Rc<Vec<Pipeline>>>>,
 }
 
 impl CommandRegistry {
     pub fn new() -> Self {
+        let script_name = env::args().next().unwrap_or_else(|| "rust-shell".to_string());
+
+        let mut variables: HashMap<String, Variable> = env::vars()
+            .map(|(name, value)| (name, Variable { value: Value::Scalar(value), exported: true }))
+            .collect();
+
+        let shell_level = variables.get("SHLVL")
+            .and_then(|var| var.value.scalar()?.parse::<i32>().ok())
+            .unwrap_or(0);
+        variables.insert("SHLVL".to_string(), Variable { value: Value::Scalar((shell_level + 1).to_string()), exported: true });
+
         CommandRegistry {
             builtins: HashMap::new(),
-            executables: HashMap::new(),
 
             history: RefCell::new(Vec::new()),
+            variables: RefCell::new(variables),
+            parameters: RefCell::new(ShellParameters::new(script_name)),
+            jobs: RefCell::new(JobTable::default()),
+            traps: RefCell::new(Traps::default()),
+            command_cache: RefCell::new(CommandCache::default()),
+            functions: RefCell::new(HashMap::new()),
         }
     }
     
-    pub fn get_builtin(&self, name: &str) -> Option<&Box<dyn Command>> {
-        self.builtins.get(name)
+    pub fn get_builtin(&self, name: &str) -> Option<&dyn Command> {
+        self.builtins.get(name).map(|command| command.as_ref())
     }
 
-    // pub fn get_executable(&self, name: &str) -> Option<&String> {
-    //     self.executables.get(name)
-    // }
-    
     pub fn get_command_names(&self) -> Vec<String> {
         let mut names: Vec<String> = self.builtins.keys().cloned().collect();
-        names.extend(self.executables.keys().cloned());
+        let path_var = self.get_variable("PATH").unwrap_or_default();
+        names.extend(self.command_cache().names(&path_var));
+        names.extend(self.functions.borrow().keys().cloned());
         
         names.sort();
         names.dedup();
@@ -46,9 +77,381 @@ impl CommandRegistry {
         self.builtins.insert(command.get_name().to_string(), command);
     }
 
+    pub fn get_function(&self, name: &str) -> Option<Rc<Vec<Pipeline>>> {
+        self.functions.borrow().get(name).cloned()
+    }
+
+    pub fn define_function(&self, name: &str, body: Rc<Vec<Pipeline>>) {
+        self.functions.borrow_mut().insert(name.to_string(), body);
+    }
+
+    pub fn get_variable(&self, name: &str) -> Option<String> {
+        if let Some(value) = self.parameters.borrow_mut().lookup(name) {
+            return Some(value);
+        }
+        self.variables.borrow().get(name).and_then(|var| var.value.scalar())
+    }
+
+    pub fn set_variable(&self, name: &str, value: &str) -> Result<(), String> {
+        if self.parameters.borrow_mut().assign(name, value)? {
+            return Ok(());
+        }
+        self.variable_changed(name);
+
+        let mut variables = self.variables.borrow_mut();
+        match variables.get_mut(name) {
+            Some(Variable { value: Value::Indexed(elements), .. }) => {
+                elements.insert(0, value.to_string());
+            }
+            Some(Variable { value: Value::Associative(elements), .. }) => {
+                elements.insert("0".to_string(), value.to_string());
+            }
+            Some(var) => var.value = Value::Scalar(value.to_string()),
+            None => {
+                variables.insert(name.to_string(), Variable { value: Value::Scalar(value.to_string()), exported: false });
+            }
+        }
+        if self.is_option_set("allexport") && let Some(var) = variables.get_mut(name) {
+            var.exported = true;
+        }
+        Ok(())
+    }
+
+    pub fn get_value(&self, name: &str) -> Option<Value> {
+        self.variables.borrow().get(name).map(|var| var.value.clone())
+    }
+
+    pub fn is_exported(&self, name: &str) -> bool {
+        self.variables.borrow().get(name).is_some_and(|var| var.exported)
+    }
+
+    pub fn get_variable_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.variables.borrow().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Every element of an array (or the positional parameters for `@`/`*`),
+    /// in index order.
+    pub fn get_array_values(&self, name: &str) -> Vec<String> {
+        match name {
+            "@" | "*" => self.get_positional_params(),
+            _ => self.variables.borrow().get(name).map(|var| var.value.values()).unwrap_or_default(),
+        }
+    }
+
+    pub fn get_array_keys(&self, name: &str) -> Vec<String> {
+        match name {
+            "@" | "*" => (1..=self.get_positional_params().len()).map(|i| i.to_string()).collect(),
+            _ => self.variables.borrow().get(name).map(|var| var.value.keys()).unwrap_or_default(),
+        }
+    }
+
+    pub fn get_array_element(&self, name: &str, subscript: &str) -> Result<Option<String>, String> {
+        let Some(value) = self.get_value(name) else {
+            return Ok(None);
+        };
+
+        match value {
+            Value::Associative(_) => Ok(value.element(subscript)),
+            _ => {
+                let index = self.evaluate_index(subscript)?;
+                Ok(value.resolve_index(index).and_then(|index| value.element(&index.to_string())))
+            }
+        }
+    }
+
+    pub fn set_array_element(&self, name: &str, subscript: &str, element: &str) -> Result<(), String> {
+        self.check_writable(name)?;
+        let mut value = self.get_value(name).unwrap_or(Value::Indexed(BTreeMap::new()));
+
+        if let Value::Associative(elements) = &mut value {
+            elements.insert(subscript.to_string(), element.to_string());
+        } else {
+            let index = value.resolve_index(self.evaluate_index(subscript)?)
+                .ok_or_else(|| format!("{}[{}]: bad array subscript", name, subscript))?;
+            if let Value::Scalar(current) = value {
+                value = Value::Indexed(BTreeMap::from([(0, current)]));
+            }
+            if let Value::Indexed(elements) = &mut value {
+                elements.insert(index, element.to_string());
+            }
+        }
+
+        self.store_value(name, value);
+        Ok(())
+    }
+
+    /// Replaces a whole array from an `name=(...)` literal. Elements without
+    /// an explicit `[key]=` continue from the previous index.
+    pub fn set_array(&self, name: &str, elements: Vec<(Option<String>, String)>) -> Result<(), String> {
+        self.check_writable(name)?;
+        let is_associative = matches!(self.get_value(name), Some(Value::Associative(_)));
+
+        let value = if is_associative {
+            let mut map = BTreeMap::new();
+            for (key, element) in elements {
+                let key = key.ok_or_else(|| format!("{}: {}: must use subscript when assigning associative array", name, element))?;
+                map.insert(key, element);
+            }
+            Value::Associative(map)
+        } else {
+            let mut map = BTreeMap::new();
+            let mut next_index = 0;
+            for (key, element) in elements {
+                if let Some(key) = key {
+                    next_index = usize::try_from(self.evaluate_index(&key)?)
+                        .map_err(|_| format!("{}[{}]: bad array subscript", name, key))?;
+                }
+                map.insert(next_index, element);
+                next_index += 1;
+            }
+            Value::Indexed(map)
+        };
+
+        self.store_value(name, value);
+        Ok(())
+    }
+
+    pub fn check_writable(&self, name: &str) -> Result<(), String> {
+        match self.parameters.borrow().is_readonly(name) {
+            true => Err(format!("{}: readonly variable", name)),
+            false => Ok(()),
+        }
+    }
+
+    fn store_value(&self, name: &str, value: Value) {
+        self.variable_changed(name);
+        let exported = self.is_option_set("allexport");
+        let mut variables = self.variables.borrow_mut();
+        match variables.get_mut(name) {
+            Some(var) => {
+                var.value = value;
+                var.exported |= exported;
+            }
+            None => {
+                variables.insert(name.to_string(), Variable { value, exported });
+            }
+        }
+    }
+
+    /// Backs `declare -a` / `declare -A`, turning an existing scalar into
+    /// element 0 of a new indexed array.
+    pub fn declare_array(&self, name: &str, associative: bool) -> Result<(), String> {
+        self.check_writable(name)?;
+        let mut variables = self.variables.borrow_mut();
+
+        match (variables.get_mut(name), associative) {
+            (None, _) => {
+                let value = if associative { Value::Associative(BTreeMap::new()) } else { Value::Indexed(BTreeMap::new()) };
+                variables.insert(name.to_string(), Variable { value, exported: false });
+            }
+            (Some(Variable { value: Value::Associative(_), .. }), false) => {
+                return Err(format!("{}: cannot convert associative to indexed array", name));
+            }
+            (Some(Variable { value: Value::Indexed(_), .. }), true) => {
+                return Err(format!("{}: cannot convert indexed to associative array", name));
+            }
+            (Some(var), associative) => {
+                if let Value::Scalar(current) = &var.value {
+                    var.value = if associative {
+                        Value::Associative(BTreeMap::from([("0".to_string(), current.clone())]))
+                    } else {
+                        Value::Indexed(BTreeMap::from([(0, current.clone())]))
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates an integer operand (array subscripts, substring offsets):
+    /// a number, or the name of a variable holding one (unset counts as 0).
+    pub fn evaluate_integer(&self, expression: &str) -> Option<i64> {
+        let expression = expression.trim();
+        if let Ok(value) = expression.parse::<i64>() {
+            return Some(value);
+        }
+        if crate::utils::is_valid_name(expression) {
+            return Some(self.get_variable(expression).and_then(|value| value.trim().parse().ok()).unwrap_or(0));
+        }
+        None
+    }
+
+    fn evaluate_index(&self, subscript: &str) -> Result<i64, String> {
+        self.evaluate_integer(subscript).ok_or_else(|| format!("{}: bad array subscript", subscript.trim()))
+    }
+
+    pub fn unset_variable(&self, name: &str) {
+        self.variable_changed(name);
+        self.variables.borrow_mut().remove(name);
+    }
+
+    /// Commands found through the old `PATH` may resolve differently now.
+    fn variable_changed(&self, name: &str) {
+        if name == "PATH" {
+            self.command_cache().clear();
+        }
+    }
+
+    pub fn get_exported_variables(&self) -> Vec<(String, String)> {
+        self.variables.borrow().iter()
+            .filter(|(_, var)| var.exported)
+            .filter_map(|(name, var)| match &var.value {
+                Value::Scalar(value) => Some((name.clone(), value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get_positional_params(&self) -> Vec<String> {
+        self.parameters.borrow().positional.clone()
+    }
+
+    pub fn set_positional_params(&self, params: Vec<String>) -> Vec<String> {
+        std::mem::replace(&mut self.parameters.borrow_mut().positional, params)
+    }
+
+    pub fn set_script_name(&self, name: &str) {
+        self.parameters.borrow_mut().script_name = name.to_string();
+    }
+
+    pub fn get_last_status(&self) -> i32 {
+        self.parameters.borrow().last_status
+    }
+
+    pub fn set_last_status(&self, status: i32) {
+        self.parameters.borrow_mut().last_status = status;
+    }
+
+    pub fn set_last_background_pid(&self, pid: u32) {
+        self.parameters.borrow_mut().last_background_pid = Some(pid);
+    }
+
+    pub fn jobs(&self) -> RefMut<'_, JobTable> {
+        self.jobs.borrow_mut()
+    }
+
+    pub fn traps(&self) -> RefMut<'_, Traps> {
+        self.traps.borrow_mut()
+    }
+
+    pub fn command_cache(&self) -> RefMut<'_, CommandCache> {
+        self.command_cache.borrow_mut()
+    }
+
+    pub fn set_last_argument(&self, argument: &str) {
+        self.parameters.borrow_mut().last_argument = argument.to_string();
+    }
+
+    pub fn set_line_number(&self, line_number: usize) -> usize {
+        std::mem::replace(&mut self.parameters.borrow_mut().line_number, line_number)
+    }
+
+    pub fn is_sourcing(&self) -> bool {
+        self.parameters.borrow().source_depth > 0
+    }
+
+    pub fn enter_source(&self) {
+        self.parameters.borrow_mut().source_depth += 1;
+    }
+
+    pub fn leave_source(&self) {
+        self.parameters.borrow_mut().source_depth -= 1;
+    }
+
+    pub fn in_function(&self) -> bool {
+        self.parameters.borrow().function_depth > 0
+    }
 
-    fn register_executable(&mut self, name: &str, path: &str) {
-        self.executables.insert(name.to_string(), path.to_string());
+    pub fn enter_function(&self) {
+        self.parameters.borrow_mut().function_depth += 1;
+    }
+
+    pub fn leave_function(&self) {
+        self.parameters.borrow_mut().function_depth -= 1;
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.parameters.borrow().interactive
+    }
+
+    pub fn set_interactive(&self, interactive: bool) {
+        self.parameters.borrow_mut().interactive = interactive;
+    }
+
+    pub fn has_job_control(&self) -> bool {
+        self.parameters.borrow().job_control
+    }
+
+    pub fn set_job_control(&self, enabled: bool) {
+        self.parameters.borrow_mut().job_control = enabled;
+    }
+
+    pub fn is_option_set(&self, name: &str) -> bool {
+        self.parameters.borrow().options.contains(name)
+    }
+
+    pub fn set_option(&self, name: &str, enabled: bool) -> Result<(), String> {
+        let name = SHELL_OPTIONS.iter().find(|option| **option == name)
+            .ok_or_else(|| format!("{}: invalid option name", name))?;
+        self.toggle_option(name, enabled);
+        Ok(())
+    }
+
+    /// Sets an option by its `set` flag letter, such as `e` for `errexit`.
+    pub fn set_option_flag(&self, flag: char, enabled: bool) -> Result<(), String> {
+        let (_, name) = OPTION_FLAGS.iter().find(|(letter, _)| *letter == flag)
+            .ok_or_else(|| format!("-{}: invalid option", flag))?;
+        // Restricted mode can't be turned off once it's on.
+        if *name == "restricted" && !enabled && self.is_option_set(name) {
+            return Err(format!("+{}: invalid option", flag));
+        }
+        self.toggle_option(name, enabled);
+        Ok(())
+    }
+
+    /// Like `set_option`, for the options `shopt` manages.
+    pub fn set_shopt(&self, name: &str, enabled: bool) -> Result<(), String> {
+        let name = SHOPT_OPTIONS.iter().find(|option| **option == name)
+            .ok_or_else(|| format!("{}: invalid shell option name", name))?;
+        self.toggle_option(name, enabled);
+        Ok(())
+    }
+
+    fn toggle_option(&self, name: &'static str, enabled: bool) {
+        let mut parameters = self.parameters.borrow_mut();
+        if enabled {
+            parameters.options.insert(name);
+        } else {
+            parameters.options.remove(name);
+        }
+    }
+
+    /// `set -e` is ignored while a condition is being evaluated, including
+    /// inside any function it calls.
+    pub fn in_condition(&self) -> bool {
+        self.parameters.borrow().condition_depth > 0
+    }
+
+    pub fn enter_condition(&self) {
+        self.parameters.borrow_mut().condition_depth += 1;
+    }
+
+    pub fn leave_condition(&self) {
+        self.parameters.borrow_mut().condition_depth -= 1;
+    }
+
+    pub fn set_fatal_error(&self) {
+        self.parameters.borrow_mut().fatal_error = true;
+    }
+
+    pub fn take_fatal_error(&self) -> bool {
+        std::mem::take(&mut self.parameters.borrow_mut().fatal_error)
+    }
+
+    pub fn set_input_flag(&self, flag: char) {
+        self.parameters.borrow_mut().input_flag = Some(flag);
     }
 
     pub fn add_history_entry(&self, cmd: &str) {
@@ -66,6 +469,14 @@ impl CommandRegistry {
         Ok(())
     }
 
+    /// Interactive shells write their history to `$HISTFILE` on the way out.
+    pub fn save_history(&self) {
+        let path = self.get_variable("HISTFILE").unwrap_or_default();
+        if !path.is_empty() && self.is_interactive() {
+            let _ = self.write_history_to_file(&path, false, false);
+        }
+    }
+
     pub fn write_history_to_file(&self, path: &str, append: bool, is_exit: bool) -> Result<(), String> {
         let mut file = open_file(path, append)?;
         let history = self.history.borrow();
@@ -91,45 +502,38 @@ impl CommandRegistry {
     }
 
 
-    fn scan_path_executables(&mut self) {
-        let path_var = env::var("PATH").unwrap_or_default();
-
-        let paths = path_var.split(':');
-
-        for path in paths {
-            if let Ok(entries) = fs::read_dir(path) {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let file_name = entry.file_name().into_string().unwrap_or_default();
-
-                        if self.executables.contains_key(&file_name) {
-                            continue;
-                        }
-
-                        let full_path = format!("{}/{}", path, file_name);
-                        if fs::metadata(&full_path).map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false) {
-                            self.register_executable(&file_name, &full_path);
-                        }
-                    }
-                }
-            }
+    /// Names containing a slash are paths already and skip the PATH search;
+    /// other commands are looked up in the cache first.
+    pub fn get_executable_path(&self, command: &str) -> Option<String> {
+        if command.contains('/') {
+            return is_executable(command).then(|| command.to_string());
+        }
+        if let Some(path) = self.command_cache().get(command) {
+            return Some(path);
         }
+        let path = self.search_path(command, |m| m.is_file() && m.permissions().mode() & 0o111 != 0)?;
+        self.command_cache().remember(command, &path);
+        Some(path)
     }
-    
 
-    pub fn get_executable_path(&self, command: &str) -> Option<String> {
-        let path_var = env::var("PATH").unwrap_or_default();
+    /// Files given to `source` only need to be readable, not executable.
+    pub fn get_source_path(&self, name: &str) -> Option<String> {
+        self.search_path(name, |m| m.is_file())
+    }
+
+    fn search_path(&self, name: &str, accept: impl Fn(&fs::Metadata) -> bool) -> Option<String> {
+        let path_var = self.get_variable("PATH").unwrap_or_default();
 
         let paths = path_var.split(':');
 
         for path in paths {
-            let full_path = format!("{}/{}", path, command);
-            if fs::metadata(&full_path).map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false) {
+            let full_path = format!("{}/{}", path, name);
+            if fs::metadata(&full_path).map(|m| accept(&m)).unwrap_or(false) {
                 return Some(full_path);
             }
         }
 
-        return None;
+        None
     }
 
 }
@@ -143,8 +547,28 @@ impl Default for CommandRegistry {
         registry.register_builtin(Box::new(PwdCommand));
         registry.register_builtin(Box::new(CdCommand));
         registry.register_builtin(Box::new(HistoryCommand));
-
-        registry.scan_path_executables();
+        registry.register_builtin(Box::new(SourceCommand::new("source")));
+        registry.register_builtin(Box::new(SourceCommand::new(".")));
+        registry.register_builtin(Box::new(ReturnCommand));
+        registry.register_builtin(Box::new(DeclareCommand));
+        registry.register_builtin(Box::new(JobsCommand));
+        registry.register_builtin(Box::new(FgCommand));
+        registry.register_builtin(Box::new(BgCommand));
+        registry.register_builtin(Box::new(DisownCommand));
+        registry.register_builtin(Box::new(WaitCommand));
+        registry.register_builtin(Box::new(TrapCommand));
+        registry.register_builtin(Box::new(ReadCommand));
+        registry.register_builtin(Box::new(SetCommand));
+        registry.register_builtin(Box::new(MapfileCommand::new("mapfile")));
+        registry.register_builtin(Box::new(MapfileCommand::new("readarray")));
+        registry.register_builtin(Box::new(HashCommand));
+        registry.register_builtin(Box::new(ExecCommand));
+        registry.register_builtin(Box::new(TimesCommand));
+        registry.register_builtin(Box::new(UlimitCommand));
+        registry.register_builtin(Box::new(UmaskCommand));
+        registry.register_builtin(Box::new(ShoptCommand));
+        registry.register_builtin(Box::new(CommandCommand));
+        registry.register_builtin(Box::new(BuiltinCommand));
 
         registry
     }
@@ -0,0 +1,25 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+pub struct ReturnCommand;
+
+impl Command for ReturnCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        if !registry.is_sourcing() && !registry.in_function() {
+            return Err("return: can only `return' from a function or sourced script".to_string());
+        }
+
+        let code = match args.first() {
+            Some(arg) => arg.parse::<i32>()
+                .map_err(|_| format!("return: {}: numeric argument required", arg))?,
+            None => registry.get_last_status(),
+        };
+
+        Ok(ShellStatus::Return(code & 0xff))
+    }
+
+    fn get_name(&self) -> &str {
+        "return"
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::variables::SHELL_OPTIONS;
+use crate::utils::quote;
+
+pub struct SetCommand;
+
+impl Command for SetCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        if args.is_empty() {
+            return print_variables(registry, output);
+        }
+
+        let mut iter = args.iter().peekable();
+        while let Some(arg) = iter.peek() {
+            match arg.as_str() {
+                "--" => {
+                    iter.next();
+                    registry.set_positional_params(iter.cloned().collect());
+                    return Ok(ShellStatus::Continue);
+                }
+                // `set -` ends the options and turns off `-x` and `-v`.
+                "-" => {
+                    iter.next();
+                    registry.set_option("xtrace", false)?;
+                    registry.set_option("verbose", false)?;
+                    break;
+                }
+                "-o" | "+o" => {
+                    let enable = arg.starts_with('-');
+                    iter.next();
+                    match iter.next() {
+                        Some(name) => {
+                            if let Err(e) = registry.set_option(name, enable) {
+                                writeln!(error, "set: {}", e).map_err(|e| e.to_string())?;
+                                return Ok(ShellStatus::Status(2));
+                            }
+                        }
+                        None => return print_options(registry, enable, output),
+                    }
+                }
+                flags if flags.starts_with(['-', '+']) && flags.len() > 1 => {
+                    let (sign, letters) = flags.split_at(1);
+                    for letter in letters.chars() {
+                        if registry.set_option_flag(letter, sign == "-").is_err() {
+                            return usage_error(error, &format!("{}{}: invalid option", sign, letter));
+                        }
+                    }
+                    iter.next();
+                }
+                _ => break,
+            }
+        }
+
+        if iter.peek().is_some() {
+            registry.set_positional_params(iter.cloned().collect());
+        }
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "set"
+    }
+}
+
+/// Bad options are usage errors, which `set` reports with status 2.
+fn usage_error(error: &mut dyn Write, message: &str) -> Result<ShellStatus, String> {
+    writeln!(error, "set: {}", message).map_err(|e| e.to_string())?;
+    writeln!(error, "set: usage: set [-aefruvx] [-o option-name] [--] [-] [arg ...]").map_err(|e| e.to_string())?;
+    Ok(ShellStatus::Status(2))
+}
+
+/// `set -o` shows a table of options; `set +o` prints the commands that
+/// would restore them.
+fn print_options(registry: &CommandRegistry, table: bool, output: &mut dyn Write) -> Result<ShellStatus, String> {
+    for name in SHELL_OPTIONS {
+        let enabled = registry.is_option_set(name);
+        let line = if table {
+            format!("{:<15}\t{}", name, if enabled { "on" } else { "off" })
+        } else {
+            format!("set {}o {}", if enabled { '-' } else { '+' }, name)
+        };
+        writeln!(output, "{}", line).map_err(|e| e.to_string())?;
+    }
+    Ok(ShellStatus::Continue)
+}
+
+fn print_variables(registry: &CommandRegistry, output: &mut dyn Write) -> Result<ShellStatus, String> {
+    for name in registry.get_variable_names() {
+        let value = registry.get_variable(&name).unwrap_or_default();
+        writeln!(output, "{}={}", name, quote(&value)).map_err(|e| e.to_string())?;
+    }
+    Ok(ShellStatus::Continue)
+}
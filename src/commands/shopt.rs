@@ -0,0 +1,102 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::variables::{SHELL_OPTIONS, SHOPT_OPTIONS};
+
+pub struct ShoptCommand;
+
+impl Command for ShoptCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut set = false;
+        let mut unset = false;
+        let mut quiet = false;
+        let mut reusable = false;
+        // `-o` works on the `set -o` options instead.
+        let mut set_options = false;
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    's' => set = true,
+                    'u' => unset = true,
+                    'q' => quiet = true,
+                    'p' => reusable = true,
+                    'o' => set_options = true,
+                    _ => {
+                        writeln!(error, "shopt: -{}: invalid option", flag).map_err(|e| e.to_string())?;
+                        writeln!(error, "shopt: usage: shopt [-pqsu] [-o] [optname ...]").map_err(|e| e.to_string())?;
+                        return Ok(ShellStatus::Status(2));
+                    }
+                }
+            }
+        }
+        let names: Vec<&String> = iter.collect();
+
+        if set && unset {
+            writeln!(error, "shopt: cannot set and unset shell options simultaneously").map_err(|e| e.to_string())?;
+            return Ok(ShellStatus::Status(1));
+        }
+
+        let known = if set_options { SHELL_OPTIONS } else { SHOPT_OPTIONS };
+        let mut status = 0;
+
+        // Without names, list every option (only those on or off for `-s`
+        // and `-u`).
+        if names.is_empty() {
+            for name in known {
+                let enabled = registry.is_option_set(name);
+                if (set && !enabled) || (unset && enabled) {
+                    continue;
+                }
+                print_option(name, enabled, reusable, set_options, output)?;
+            }
+            return Ok(ShellStatus::Continue);
+        }
+
+        for name in names {
+            if !known.contains(&name.as_str()) {
+                let kind = if set_options { "option name" } else { "shell option name" };
+                writeln!(error, "shopt: {}: invalid {}", name, kind).map_err(|e| e.to_string())?;
+                status = 1;
+                continue;
+            }
+
+            if set || unset {
+                if set_options {
+                    registry.set_option(name, set)?;
+                } else {
+                    registry.set_shopt(name, set)?;
+                }
+                continue;
+            }
+
+            let enabled = registry.is_option_set(name);
+            if !enabled {
+                status = 1;
+            }
+            if !quiet {
+                print_option(name, enabled, reusable, set_options, output)?;
+            }
+        }
+        Ok(ShellStatus::Status(status))
+    }
+
+    fn get_name(&self) -> &str {
+        "shopt"
+    }
+}
+
+/// A table row like `set -o` prints, or with `-p` the command that restores
+/// the setting.
+fn print_option(name: &str, enabled: bool, reusable: bool, set_options: bool, output: &mut dyn Write) -> Result<(), String> {
+    let line = match (reusable, set_options) {
+        (false, _) => format!("{:<15}\t{}", name, if enabled { "on" } else { "off" }),
+        (true, false) => format!("shopt {} {}", if enabled { "-s" } else { "-u" }, name),
+        (true, true) => format!("set {}o {}", if enabled { '-' } else { '+' }, name),
+    };
+    writeln!(output, "{}", line).map_err(|e| e.to_string())
+}
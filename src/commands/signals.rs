@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+const SIGNAL_COUNT: usize = 65;
+
+/// Signals that arrived for a trap and have not been handled yet.
+static PENDING: [AtomicBool; SIGNAL_COUNT] = [const { AtomicBool::new(false) }; SIGNAL_COUNT];
+
+/// The last signal that should cut a blocking builtin such as `wait` short:
+/// Ctrl-C in an interactive shell, or any trapped signal.
+static INTERRUPTED: AtomicI32 = AtomicI32::new(0);
+
+/// SIGINT reached the shell itself while it waited for a foreground job.
+static WAIT_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Signal names as `trap` and `kill -l` spell them, without the `SIG` prefix.
+const SIGNAL_NAMES: &[(i32, &str)] = &[
+    (libc::SIGHUP, "HUP"),
+    (libc::SIGINT, "INT"),
+    (libc::SIGQUIT, "QUIT"),
+    (libc::SIGILL, "ILL"),
+    (libc::SIGTRAP, "TRAP"),
+    (libc::SIGABRT, "ABRT"),
+    (libc::SIGBUS, "BUS"),
+    (libc::SIGFPE, "FPE"),
+    (libc::SIGKILL, "KILL"),
+    (libc::SIGUSR1, "USR1"),
+    (libc::SIGSEGV, "SEGV"),
+    (libc::SIGUSR2, "USR2"),
+    (libc::SIGPIPE, "PIPE"),
+    (libc::SIGALRM, "ALRM"),
+    (libc::SIGTERM, "TERM"),
+    (libc::SIGCHLD, "CHLD"),
+    (libc::SIGCONT, "CONT"),
+    (libc::SIGSTOP, "STOP"),
+    (libc::SIGTSTP, "TSTP"),
+    (libc::SIGTTIN, "TTIN"),
+    (libc::SIGTTOU, "TTOU"),
+    (libc::SIGURG, "URG"),
+    (libc::SIGXCPU, "XCPU"),
+    (libc::SIGXFSZ, "XFSZ"),
+    (libc::SIGVTALRM, "VTALRM"),
+    (libc::SIGPROF, "PROF"),
+    (libc::SIGWINCH, "WINCH"),
+    (libc::SIGIO, "IO"),
+    (libc::SIGSYS, "SYS"),
+];
+
+/// What a trap is attached to: a real signal or one of the shell's own
+/// `EXIT`, `DEBUG`, `ERR` and `RETURN` conditions.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrapEvent {
+    Exit,
+    Signal(i32),
+    Debug,
+    Err,
+    Return,
+}
+
+impl TrapEvent {
+    /// Accepts signal numbers and names with or without `SIG`, in any case.
+    pub fn parse(spec: &str) -> Option<Self> {
+        if let Ok(number) = spec.parse::<i32>() {
+            return match number {
+                0 => Some(TrapEvent::Exit),
+                _ => SIGNAL_NAMES.iter().any(|(signal, _)| *signal == number).then_some(TrapEvent::Signal(number)),
+            };
+        }
+
+        let upper = spec.to_ascii_uppercase();
+        match upper.as_str() {
+            "EXIT" => return Some(TrapEvent::Exit),
+            "DEBUG" => return Some(TrapEvent::Debug),
+            "ERR" => return Some(TrapEvent::Err),
+            "RETURN" => return Some(TrapEvent::Return),
+            _ => {}
+        }
+
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        SIGNAL_NAMES.iter()
+            .find(|(_, candidate)| *candidate == name)
+            .map(|(signal, _)| TrapEvent::Signal(*signal))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            TrapEvent::Exit => "EXIT".to_string(),
+            TrapEvent::Signal(signal) => format!("SIG{}", signal_name(*signal).unwrap_or("?")),
+            TrapEvent::Debug => "DEBUG".to_string(),
+            TrapEvent::Err => "ERR".to_string(),
+            TrapEvent::Return => "RETURN".to_string(),
+        }
+    }
+}
+
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    SIGNAL_NAMES.iter().find(|(number, _)| *number == signal).map(|(_, name)| *name)
+}
+
+/// Every signal `trap -l` lists, in number order.
+pub fn signal_list() -> Vec<(i32, &'static str)> {
+    let mut signals = SIGNAL_NAMES.to_vec();
+    signals.sort();
+    signals
+}
+
+/// The trap table. An empty action means the signal is ignored.
+#[derive(Default)]
+pub struct Traps {
+    actions: BTreeMap<TrapEvent, String>,
+    running: bool,
+    /// The function call being run has set the RETURN trap, so it fires
+    /// when that call returns.
+    return_set_in_call: bool,
+}
+
+impl Traps {
+    pub fn get(&self, event: TrapEvent) -> Option<&String> {
+        self.actions.get(&event)
+    }
+
+    pub fn set(&mut self, event: TrapEvent, action: &str) {
+        if let TrapEvent::Signal(signal) = event {
+            if action.is_empty() {
+                set_disposition(signal, libc::SIG_IGN);
+            } else {
+                set_disposition(signal, record_trap as extern "C" fn(libc::c_int) as libc::sighandler_t);
+            }
+        }
+        if event == TrapEvent::Return {
+            self.return_set_in_call = true;
+        }
+        self.actions.insert(event, action.to_string());
+    }
+
+    /// `trap - SIG`: forgets the action and gives the signal back the
+    /// disposition the shell started with.
+    pub fn reset(&mut self, event: TrapEvent, interactive: bool) {
+        if let TrapEvent::Signal(signal) = event {
+            set_disposition(signal, shell_disposition(signal, interactive));
+        }
+        self.actions.remove(&event);
+    }
+
+    pub fn take(&mut self, event: TrapEvent) -> Option<String> {
+        self.actions.remove(&event)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&TrapEvent, &String)> {
+        self.actions.iter()
+    }
+
+    /// Starts a function call, returning the caller's flag for `end_call`
+    /// to restore.
+    pub fn begin_call(&mut self) -> bool {
+        std::mem::take(&mut self.return_set_in_call)
+    }
+
+    /// Ends a function call: whether it set the RETURN trap.
+    pub fn end_call(&mut self, caller: bool) -> bool {
+        std::mem::replace(&mut self.return_set_in_call, caller)
+    }
+
+    /// Handlers run one at a time, and `ERR`/`DEBUG` don't fire from inside
+    /// another handler.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    /// The signals `trap ''` ignores, which commands inherit ignored.
+    pub fn ignored_signals(&self) -> Vec<i32> {
+        self.actions.iter()
+            .filter_map(|(event, action)| match event {
+                TrapEvent::Signal(signal) if action.is_empty() => Some(*signal),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Subshells keep ignored signals ignored but drop every other trap.
+    pub fn reset_for_subshell(&mut self) {
+        self.actions.retain(|event, action| {
+            if let TrapEvent::Signal(signal) = event {
+                set_disposition(*signal, if action.is_empty() { libc::SIG_IGN } else { libc::SIG_DFL });
+            }
+            action.is_empty()
+        });
+        self.running = false;
+    }
+}
+
+extern "C" fn record_interrupt(signal: libc::c_int) {
+    INTERRUPTED.store(signal, Ordering::SeqCst);
+}
+
+extern "C" fn record_wait_interrupt(_: libc::c_int) {
+    WAIT_INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn record_trap(signal: libc::c_int) {
+    if let Some(pending) = PENDING.get(signal as usize) {
+        pending.store(true, Ordering::SeqCst);
+    }
+    INTERRUPTED.store(signal, Ordering::SeqCst);
+}
+
+fn set_disposition(signal: libc::c_int, handler: libc::sighandler_t) -> libc::sigaction {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        let mut previous: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(signal, &action, &mut previous);
+        previous
+    }
+}
+
+fn shell_disposition(signal: libc::c_int, interactive: bool) -> libc::sighandler_t {
+    match signal {
+        libc::SIGINT if interactive => record_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        libc::SIGQUIT | libc::SIGTSTP | libc::SIGTTIN | libc::SIGTTOU if interactive => libc::SIG_IGN,
+        _ => libc::SIG_DFL,
+    }
+}
+
+/// Interactive shells survive Ctrl-C and Ctrl-\: SIGINT only interrupts what
+/// the shell is blocked on (without `SA_RESTART`), SIGQUIT is ignored.
+pub fn init_interactive_signals() {
+    for signal in [libc::SIGINT, libc::SIGQUIT] {
+        set_disposition(signal, shell_disposition(signal, true));
+    }
+}
+
+/// Returns the signal that interrupted a blocking builtin since the last
+/// call, if any.
+pub fn take_interrupt() -> Option<i32> {
+    match INTERRUPTED.swap(0, Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Whether SIGINT reached the shell during the last foreground wait. A
+/// script only gives up when it did, not when a job merely died of SIGINT
+/// sent to it alone.
+pub fn take_wait_interrupt() -> bool {
+    WAIT_INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+/// Returns the trapped signals that arrived since the last call.
+pub fn take_pending_signals() -> Vec<i32> {
+    PENDING.iter()
+        .enumerate()
+        .filter(|(_, pending)| pending.swap(false, Ordering::SeqCst))
+        .map(|(signal, _)| signal as i32)
+        .collect()
+}
+
+/// Runs `body` with SIGINT and SIGQUIT held off, as the shell does while a
+/// foreground job runs: the keyboard signals are meant for the job. SIGINT
+/// is only noted, for `take_wait_interrupt`. Trapped signals stay caught so
+/// their handlers run once the job is done, and ignored ones stay ignored.
+pub fn ignoring_interrupts<T>(body: impl FnOnce() -> T) -> T {
+    let trap_handler = record_trap as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    let wait_handler = record_wait_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    WAIT_INTERRUPTED.store(false, Ordering::SeqCst);
+    let previous: Vec<(libc::c_int, libc::sigaction)> = [(libc::SIGINT, wait_handler), (libc::SIGQUIT, libc::SIG_IGN)].into_iter()
+        .map(|(signal, handler)| (signal, set_disposition(signal, handler)))
+        .collect();
+    for (signal, action) in &previous {
+        if action.sa_sigaction == trap_handler || action.sa_sigaction == libc::SIG_IGN {
+            unsafe { libc::sigaction(*signal, action, std::ptr::null_mut()) };
+        }
+    }
+
+    let result = body();
+
+    for (signal, action) in &previous {
+        unsafe { libc::sigaction(*signal, action, std::ptr::null_mut()) };
+    }
+    result
+}
+
+/// Puts SIGINT and SIGQUIT back to their defaults, for subshells.
+pub fn restore_interrupt_signals() {
+    for signal in [libc::SIGINT, libc::SIGQUIT] {
+        set_disposition(signal, libc::SIG_DFL);
+    }
+}
+
+/// Run in children before `exec`, so programs get the default behaviour for
+/// every signal the shell ignores or handles, except those `ignored` by
+/// `trap ''`, which stay ignored.
+pub fn restore_default_signals(ignored: &[i32]) {
+    for signal in [libc::SIGINT, libc::SIGQUIT, libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU] {
+        if !ignored.contains(&signal) {
+            set_disposition(signal, libc::SIG_DFL);
+        }
+    }
+}
+
+/// Re-raises SIGINT with its default action, so a script interrupted while
+/// a foreground job ran dies the same way the job did.
+pub fn exit_on_interrupt() -> ! {
+    set_disposition(libc::SIGINT, libc::SIG_DFL);
+    unsafe {
+        libc::kill(libc::getpid(), libc::SIGINT);
+        libc::_exit(128 + libc::SIGINT)
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry, ShellExecutor};
+use super::signals::TrapEvent;
+
+pub struct SourceCommand {
+    name: &'static str,
+}
+
+impl SourceCommand {
+    pub fn new(name: &'static str) -> Self {
+        Self { name }
+    }
+}
+
+impl Command for SourceCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let file = args.first()
+            .ok_or_else(|| format!("{}: filename argument required", self.name))?;
+
+        let path = if file.contains('/') {
+            file.clone()
+        } else {
+            registry.get_source_path(file).unwrap_or_else(|| file.clone())
+        };
+
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("{}: {}: {}", self.name, file, e))?;
+
+        let saved_params = (args.len() > 1).then(|| registry.set_positional_params(args[1..].to_vec()));
+        let saved_line = registry.set_line_number(0);
+        registry.enter_source();
+
+        let executor = ShellExecutor::new(registry);
+        let mut status = executor.run_script(&source);
+        if !status.stops_execution() || matches!(status, ShellStatus::Return(_)) {
+            let trap_status = executor.run_trap(TrapEvent::Return);
+            if trap_status.stops_execution() {
+                status = trap_status;
+            }
+        }
+
+        registry.leave_source();
+        registry.set_line_number(saved_line);
+        if let Some(params) = saved_params {
+            registry.set_positional_params(params);
+        }
+
+        match status {
+            ShellStatus::Exit(code) => Ok(ShellStatus::Exit(code)),
+            ShellStatus::Return(code) => Ok(ShellStatus::Status(code)),
+            _ => Ok(ShellStatus::Status(registry.get_last_status())),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        self.name
+    }
+}
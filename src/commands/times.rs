@@ -0,0 +1,156 @@
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+/// `TIMEFORMAT` when it's unset.
+pub const DEFAULT_TIMEFORMAT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+/// The format of `time -p`.
+pub const POSIX_TIMEFORMAT: &str = "real %2R\nuser %2U\nsys %2S";
+
+pub struct TimesCommand;
+
+impl Command for TimesCommand {
+    fn execute(&self, _: &[String], _: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, _: &mut dyn Write) -> Result<ShellStatus, String> {
+        let times = CpuTimes::now();
+        writeln!(output, "{} {}", format_seconds(times.shell_user, 3, true), format_seconds(times.shell_system, 3, true))
+            .and_then(|_| writeln!(output, "{} {}", format_seconds(times.children_user, 3, true), format_seconds(times.children_system, 3, true)))
+            .map_err(|e| e.to_string())?;
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "times"
+    }
+}
+
+/// CPU time used so far by the shell itself and by the children it has
+/// waited for, as the kernel accounts it when they are reaped.
+#[derive(Clone, Copy)]
+pub struct CpuTimes {
+    pub shell_user: Duration,
+    pub shell_system: Duration,
+    pub children_user: Duration,
+    pub children_system: Duration,
+}
+
+impl CpuTimes {
+    pub fn now() -> Self {
+        let (shell_user, shell_system) = usage(libc::RUSAGE_SELF);
+        let (children_user, children_system) = usage(libc::RUSAGE_CHILDREN);
+        CpuTimes { shell_user, shell_system, children_user, children_system }
+    }
+
+    /// User and system time the shell itself spent between `earlier` and now.
+    pub fn shell_since(&self, earlier: &CpuTimes) -> (Duration, Duration) {
+        (self.shell_user.saturating_sub(earlier.shell_user), self.shell_system.saturating_sub(earlier.shell_system))
+    }
+}
+
+/// CPU time of particular children, summed from the `rusage` that `wait4`
+/// returns as each one is reaped.
+#[derive(Clone, Copy, Default)]
+pub struct ChildTimes {
+    pub user: Duration,
+    pub system: Duration,
+}
+
+impl ChildTimes {
+    pub fn from_rusage(usage: &libc::rusage) -> Self {
+        ChildTimes { user: duration(usage.ru_utime), system: duration(usage.ru_stime) }
+    }
+}
+
+impl std::ops::AddAssign for ChildTimes {
+    fn add_assign(&mut self, other: ChildTimes) {
+        self.user += other.user;
+        self.system += other.system;
+    }
+}
+
+fn usage(who: libc::c_int) -> (Duration, Duration) {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(who, &mut usage) };
+    (duration(usage.ru_utime), duration(usage.ru_stime))
+}
+
+fn duration(time: libc::timeval) -> Duration {
+    Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000)
+}
+
+/// Expands a `TIMEFORMAT` string. `%R`, `%U` and `%S` are the real, user
+/// and system seconds, optionally with a precision digit and `l` for the
+/// `1m2.345s` form; `%P` is the CPU percentage and `%%` a literal `%`.
+pub fn format_times(format: &str, real: Duration, user: Duration, system: Duration) -> Result<String, String> {
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        if chars.next_if_eq(&'%').is_some() {
+            text.push('%');
+            continue;
+        }
+
+        let precision = chars.next_if(char::is_ascii_digit).map(|digit| digit.to_digit(10).unwrap().min(3) as usize);
+        let long = chars.next_if_eq(&'l').is_some();
+        let value = match chars.next() {
+            Some('R') => real,
+            Some('U') => user,
+            Some('S') => system,
+            Some('P') if precision.is_none() && !long => {
+                let percent = if real.is_zero() { 0.0 } else { (user + system).as_secs_f64() / real.as_secs_f64() * 100.0 };
+                text.push_str(&format!("{:.2}", percent));
+                continue;
+            }
+            Some(other) => return Err(format!("TIMEFORMAT: `{}': invalid format character", other)),
+            None => return Err("TIMEFORMAT: `%': missing format character".to_string()),
+        };
+        text.push_str(&format_seconds(value, precision.unwrap_or(3), long));
+    }
+
+    Ok(text)
+}
+
+/// `1.500` or, in the long form, `0m1.500s`.
+fn format_seconds(duration: Duration, precision: usize, long: bool) -> String {
+    let seconds = duration.as_secs_f64();
+    if long {
+        let minutes = (seconds / 60.0).floor();
+        format!("{}m{:.*}s", minutes, precision, seconds - minutes * 60.0)
+    } else {
+        format!("{:.*}", precision, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_each_conversion() {
+        let (real, user, system) = (Duration::from_millis(61_500), Duration::from_millis(250), Duration::from_millis(1250));
+        assert_eq!(format_times("%R %U %S", real, user, system).unwrap(), "61.500 0.250 1.250");
+        assert_eq!(format_times("%1R %0U %lR", real, user, system).unwrap(), "61.5 0 1m1.500s");
+        assert_eq!(format_times("%2lS%%", real, user, system).unwrap(), "0m1.25s%");
+        assert_eq!(format_times(DEFAULT_TIMEFORMAT, real, user, system).unwrap(), "\nreal\t1m1.500s\nuser\t0m0.250s\nsys\t0m1.250s");
+        assert_eq!(format_times(POSIX_TIMEFORMAT, real, user, system).unwrap(), "real 61.50\nuser 0.25\nsys 1.25");
+    }
+
+    #[test]
+    fn cpu_percentage() {
+        let second = Duration::from_secs(1);
+        assert_eq!(format_times("%P", 2 * second, second / 2, second / 2).unwrap(), "50.00");
+        assert_eq!(format_times("%P", Duration::ZERO, second, second).unwrap(), "0.00");
+    }
+
+    #[test]
+    fn rejects_unknown_conversions() {
+        let zero = Duration::ZERO;
+        assert_eq!(format_times("%Z", zero, zero, zero), Err("TIMEFORMAT: `Z': invalid format character".to_string()));
+        assert_eq!(format_times("%", zero, zero, zero), Err("TIMEFORMAT: `%': missing format character".to_string()));
+    }
+}
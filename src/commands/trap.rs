@@ -0,0 +1,82 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::signals::{TrapEvent, signal_list};
+use crate::utils::quote;
+
+pub struct TrapCommand;
+
+impl Command for TrapCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut args = args;
+        match args.first().map(String::as_str) {
+            Some("-l") => return list_signals(output),
+            Some("-p") => return print_traps(&args[1..], registry, output),
+            Some("--") => args = &args[1..],
+            Some(flag) if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("trap: {}: invalid option", flag));
+            }
+            _ => {}
+        }
+
+        let Some((action, specs)) = args.split_first() else {
+            return print_traps(&[], registry, output);
+        };
+
+        // A lone signal (`trap INT`) resets it, like `trap - INT`.
+        let (action, specs) = if specs.is_empty() && TrapEvent::parse(action).is_some() {
+            ("-", args)
+        } else {
+            (action.as_str(), specs)
+        };
+
+        let mut status = 0;
+        let interactive = registry.is_interactive();
+        for spec in specs {
+            let Some(event) = TrapEvent::parse(spec) else {
+                writeln!(error, "trap: {}: invalid signal specification", spec).map_err(|e| e.to_string())?;
+                status = 1;
+                continue;
+            };
+
+            let mut traps = registry.traps();
+            if action == "-" {
+                traps.reset(event, interactive);
+            } else {
+                traps.set(event, action);
+            }
+        }
+
+        Ok(ShellStatus::Status(status))
+    }
+
+    fn get_name(&self) -> &str {
+        "trap"
+    }
+}
+
+fn list_signals(output: &mut dyn Write) -> Result<ShellStatus, String> {
+    let signals = signal_list();
+    for row in signals.chunks(5) {
+        let line: Vec<String> = row.iter().map(|(number, name)| format!("{:2}) SIG{}", number, name)).collect();
+        writeln!(output, "{}", line.join("\t")).map_err(|e| e.to_string())?;
+    }
+    Ok(ShellStatus::Continue)
+}
+
+/// Prints traps in a form that can be read back in: all of them, or only
+/// the ones named.
+fn print_traps(specs: &[String], registry: &CommandRegistry, output: &mut dyn Write) -> Result<ShellStatus, String> {
+    let mut events = Vec::new();
+    for spec in specs {
+        events.push(TrapEvent::parse(spec).ok_or_else(|| format!("trap: {}: invalid signal specification", spec))?);
+    }
+
+    let traps = registry.traps();
+    for (event, action) in traps.iter() {
+        if events.is_empty() || events.contains(event) {
+            writeln!(output, "trap -- {} {}", quote(action), event.name()).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(ShellStatus::Continue)
+}
@@ -1,31 +1,47 @@
-use std::io::Write;
+use std::io::{Read, Write};
 
 use super::{Command, ShellStatus};
 use super::CommandRegistry;
+use crate::utils::{KEYWORDS, format_function};
 
 pub struct TypeCommand;
 
 impl Command for TypeCommand {
-    fn execute(&self, args: &[String], registry: &CommandRegistry,
-         output: &mut dyn Write) -> Result<ShellStatus, String> {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read,
+         output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
         if args.is_empty()  {
             return Ok(ShellStatus::Continue);
         }
 
+        let mut status = 0;
         for arg in args {
-            if let Some(command) = registry.get_builtin(&arg) {
-                writeln!(output, "{} is a {}", arg, command.get_type()).map_err(|e| e.to_string())?;
-            } else if let Some(executable_path) = registry.get_executable_path(&arg) {
-                writeln!(output, "{} is {}", arg, executable_path).map_err(|e| e.to_string())?;
-            } else {
-                return Err(format!("{}: not found", arg))?;
+            if !describe(arg, registry, registry.get_executable_path(arg), output)? {
+                writeln!(error, "{}: not found", arg).map_err(|e| e.to_string())?;
+                status = 1;
             }
         }
 
-        Ok(ShellStatus::Continue)
+        Ok(ShellStatus::Status(status))
     }
 
     fn get_name(&self) -> &str {
         "type"
     }
-}
\ No newline at end of file
+}
+
+/// Says what `name` would run as, given where it was found on disk if
+/// anywhere. Returns false if it's nothing the shell knows.
+pub(super) fn describe(name: &str, registry: &CommandRegistry, executable_path: Option<String>, output: &mut dyn Write) -> Result<bool, String> {
+    if KEYWORDS.contains(&name) {
+        writeln!(output, "{} is a shell keyword", name).map_err(|e| e.to_string())?;
+    } else if let Some(body) = registry.get_function(name) {
+        writeln!(output, "{} is a function\n{}", name, format_function(name, &body)).map_err(|e| e.to_string())?;
+    } else if let Some(command) = registry.get_builtin(name) {
+        writeln!(output, "{} is a {}", name, command.get_type()).map_err(|e| e.to_string())?;
+    } else if let Some(executable_path) = executable_path {
+        writeln!(output, "{} is {}", name, executable_path).map_err(|e| e.to_string())?;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
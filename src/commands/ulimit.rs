@@ -0,0 +1,163 @@
+use std::ffi::CStr;
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+struct Limit {
+    option: char,
+    description: &'static str,
+    unit: Option<&'static str>,
+    /// Bytes per unit shown to the user.
+    factor: u64,
+    resource: libc::c_int,
+}
+
+const LIMITS: &[Limit] = &[
+    Limit { option: 'c', description: "core file size", unit: Some("blocks"), factor: 512, resource: libc::RLIMIT_CORE as libc::c_int },
+    Limit { option: 'f', description: "file size", unit: Some("blocks"), factor: 512, resource: libc::RLIMIT_FSIZE as libc::c_int },
+    Limit { option: 'n', description: "open files", unit: None, factor: 1, resource: libc::RLIMIT_NOFILE as libc::c_int },
+    Limit { option: 's', description: "stack size", unit: Some("kbytes"), factor: 1024, resource: libc::RLIMIT_STACK as libc::c_int },
+    Limit { option: 't', description: "cpu time", unit: Some("seconds"), factor: 1, resource: libc::RLIMIT_CPU as libc::c_int },
+    Limit { option: 'v', description: "virtual memory", unit: Some("kbytes"), factor: 1024, resource: libc::RLIMIT_AS as libc::c_int },
+];
+
+pub struct UlimitCommand;
+
+impl Command for UlimitCommand {
+    fn execute(&self, args: &[String], _: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut soft = false;
+        let mut hard = false;
+        let mut all = false;
+        // Each option may be followed by the value to set it to.
+        let mut requests: Vec<(&Limit, Option<&String>)> = Vec::new();
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    'S' => soft = true,
+                    'H' => hard = true,
+                    'a' => all = true,
+                    _ => match LIMITS.iter().find(|limit| limit.option == flag) {
+                        Some(limit) => requests.push((limit, None)),
+                        None => return usage_error(error, &format!("-{}: invalid option", flag)),
+                    },
+                }
+            }
+            if flags.ends_with(|flag| LIMITS.iter().any(|limit| limit.option == flag))
+                && let Some(value) = iter.next_if(|arg| !arg.starts_with('-'))
+                && let Some(request) = requests.last_mut() {
+                request.1 = Some(value);
+            }
+        }
+
+        if all {
+            for limit in LIMITS {
+                let value = get_limit(limit, hard && !soft)?;
+                print_limit(limit, &value, true, output)?;
+            }
+            return Ok(ShellStatus::Continue);
+        }
+
+        // Without an option, `ulimit` is about the file size.
+        if requests.is_empty() {
+            requests.push((&LIMITS[1], None));
+        }
+        if let Some(value) = iter.next()
+            && let Some(request) = requests.last_mut()
+            && request.1.is_none() {
+            request.1 = Some(value);
+        }
+
+        let described = requests.len() > 1;
+        let mut status = 0;
+        for (limit, value) in requests {
+            let result = match value {
+                Some(value) => set_limit(limit, value, soft || !hard, hard || !soft),
+                None => get_limit(limit, hard && !soft).and_then(|value| print_limit(limit, &value, described, output)),
+            };
+            if let Err(e) = result {
+                writeln!(error, "ulimit: {}", e).map_err(|e| e.to_string())?;
+                status = 1;
+            }
+        }
+        Ok(ShellStatus::Status(status))
+    }
+
+    fn get_name(&self) -> &str {
+        "ulimit"
+    }
+}
+
+fn usage_error(error: &mut dyn Write, message: &str) -> Result<ShellStatus, String> {
+    writeln!(error, "ulimit: {}", message).map_err(|e| e.to_string())?;
+    writeln!(error, "ulimit: usage: ulimit [-SHacfnstv] [limit]").map_err(|e| e.to_string())?;
+    Ok(ShellStatus::Status(2))
+}
+
+/// The current limit in the units `ulimit` shows, or `unlimited`.
+fn get_limit(limit: &Limit, hard: bool) -> Result<String, String> {
+    let (current, maximum) = read_rlimit(limit).map_err(|e| format!("{}: cannot get limit: {}", limit.description, e))?;
+    let value = if hard { maximum } else { current };
+    Ok(if value == libc::RLIM_INFINITY {
+        "unlimited".to_string()
+    } else {
+        (value / limit.factor).to_string()
+    })
+}
+
+/// `ulimit -a` and several options print each limit with its description,
+/// padded like bash's table.
+fn print_limit(limit: &Limit, value: &str, described: bool, output: &mut dyn Write) -> Result<(), String> {
+    if described {
+        let unit = match limit.unit {
+            Some(unit) => format!("({}, -{}) ", unit, limit.option),
+            None => format!("(-{}) ", limit.option),
+        };
+        write!(output, "{:<20} {:>20}", limit.description, unit).map_err(|e| e.to_string())?;
+    }
+    writeln!(output, "{}", value).map_err(|e| e.to_string())
+}
+
+/// Sets the soft limit, the hard limit or both. The value is a number,
+/// `unlimited`, or `soft` / `hard` for the current limit of that kind.
+fn set_limit(limit: &Limit, value: &str, soft: bool, hard: bool) -> Result<(), String> {
+    let (mut current, mut maximum) = read_rlimit(limit).map_err(|e| format!("{}: cannot get limit: {}", limit.description, e))?;
+    let value = match value {
+        "unlimited" => libc::RLIM_INFINITY,
+        "soft" => current,
+        "hard" => maximum,
+        _ => value.parse::<u64>().ok()
+            .and_then(|number| number.checked_mul(limit.factor))
+            .ok_or_else(|| format!("{}: invalid number", value))?,
+    };
+
+    if soft {
+        current = value;
+    }
+    if hard {
+        maximum = value;
+    }
+    let rlimit = libc::rlimit { rlim_cur: current, rlim_max: maximum };
+    if unsafe { libc::setrlimit(limit.resource as _, &rlimit) } != 0 {
+        return Err(format!("{}: cannot modify limit: {}", limit.description, last_error()));
+    }
+    Ok(())
+}
+
+fn read_rlimit(limit: &Limit) -> Result<(u64, u64), String> {
+    let mut rlimit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(limit.resource as _, &mut rlimit) } != 0 {
+        return Err(last_error());
+    }
+    Ok((rlimit.rlim_cur, rlimit.rlim_max))
+}
+
+/// The C library's message for `errno`, without Rust's `(os error N)`.
+fn last_error() -> String {
+    let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+    unsafe { CStr::from_ptr(libc::strerror(errno)) }.to_string_lossy().into_owned()
+}
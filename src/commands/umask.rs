@@ -0,0 +1,166 @@
+use std::io::{Read, Write};
+
+use super::{Command, ShellStatus, CommandRegistry};
+
+pub struct UmaskCommand;
+
+impl Command for UmaskCommand {
+    fn execute(&self, args: &[String], _: &CommandRegistry, _: &mut dyn Read, output: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        let mut symbolic = false;
+
+        let mut iter = args.iter().peekable();
+        while let Some(flags) = iter.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for flag in flags.chars().skip(1) {
+                match flag {
+                    'S' => symbolic = true,
+                    _ => {
+                        writeln!(error, "umask: -{}: invalid option", flag).map_err(|e| e.to_string())?;
+                        writeln!(error, "umask: usage: umask [-S] [mode]").map_err(|e| e.to_string())?;
+                        return Ok(ShellStatus::Status(2));
+                    }
+                }
+            }
+        }
+
+        let mut mask = current_umask();
+        if let Some(mode) = iter.next() {
+            let parsed = if mode.starts_with(|c: char| c.is_ascii_digit()) {
+                parse_octal(mode)
+            } else {
+                parse_symbolic(mode, mask)
+            };
+            mask = match parsed {
+                Ok(mask) => mask,
+                Err(e) => {
+                    writeln!(error, "umask: {}", e).map_err(|e| e.to_string())?;
+                    return Ok(ShellStatus::Status(1));
+                }
+            };
+            unsafe { libc::umask(mask as libc::mode_t) };
+
+            // A new mode is only echoed back when asked for in symbolic form.
+            if !symbolic {
+                return Ok(ShellStatus::Continue);
+            }
+        }
+
+        if symbolic {
+            writeln!(output, "{}", format_symbolic(mask)).map_err(|e| e.to_string())?;
+        } else {
+            writeln!(output, "{:04o}", mask).map_err(|e| e.to_string())?;
+        }
+        Ok(ShellStatus::Continue)
+    }
+
+    fn get_name(&self) -> &str {
+        "umask"
+    }
+}
+
+/// The only way to read the mask is to set it, so put it straight back.
+fn current_umask() -> u32 {
+    let mask = unsafe { libc::umask(0o022) };
+    unsafe { libc::umask(mask) };
+    mask as u32
+}
+
+fn parse_octal(mode: &str) -> Result<u32, String> {
+    // The setuid, setgid and sticky bits are accepted but mean nothing here.
+    u32::from_str_radix(mode, 8).ok()
+        .filter(|mask| *mask <= 0o7777)
+        .map(|mask| mask & 0o777)
+        .ok_or_else(|| format!("{}: octal number out of range", mode))
+}
+
+/// Applies clauses like `u=rwx,g=rx,o=` or `go-w`. Symbolic modes name the
+/// permissions new files keep, the opposite of the mask.
+fn parse_symbolic(mode: &str, mask: u32) -> Result<u32, String> {
+    let mut allowed = !mask & 0o777;
+    let mut chars = mode.chars().peekable();
+
+    loop {
+        let mut who = 0;
+        while let Some(c) = chars.next_if(|c| "ugoa".contains(*c)) {
+            who |= match c {
+                'u' => 0o700,
+                'g' => 0o070,
+                'o' => 0o007,
+                _ => 0o777,
+            };
+        }
+        if who == 0 {
+            who = 0o777;
+        }
+
+        let operator = match chars.next() {
+            Some(c @ ('+' | '-' | '=')) => c,
+            other => return Err(format!("`{}': invalid symbolic mode operator", other.unwrap_or(' '))),
+        };
+
+        let mut permissions = 0;
+        while let Some(c) = chars.next_if(|c| *c != ',') {
+            permissions |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                _ => return Err(format!("`{}': invalid symbolic mode character", c)),
+            };
+        }
+
+        match operator {
+            '+' => allowed |= permissions & who,
+            '-' => allowed &= !(permissions & who),
+            _ => allowed = (allowed & !who) | (permissions & who),
+        }
+
+        if chars.next().is_none() {
+            return Ok(!allowed & 0o777);
+        }
+    }
+}
+
+/// `u=rwx,g=rx,o=rx` for a mask of `022`.
+fn format_symbolic(mask: u32) -> String {
+    let allowed = !mask & 0o777;
+    let class = |shift: u32| {
+        let bits = allowed >> shift;
+        [(0o4, 'r'), (0o2, 'w'), (0o1, 'x')].iter()
+            .filter(|(bit, _)| bits & bit != 0)
+            .map(|(_, c)| *c)
+            .collect::<String>()
+    };
+    format!("u={},g={},o={}", class(6), class(3), class(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octal_masks() {
+        assert_eq!(parse_octal("022"), Ok(0o022));
+        assert_eq!(parse_octal("0777"), Ok(0o777));
+        assert_eq!(parse_octal("7777"), Ok(0o777));
+        assert_eq!(parse_octal("10000"), Err("10000: octal number out of range".to_string()));
+        assert!(parse_octal("8").is_err());
+    }
+
+    #[test]
+    fn symbolic_modes_name_allowed_permissions() {
+        assert_eq!(parse_symbolic("u=rwx,g=rx,o=", 0o022), Ok(0o027));
+        assert_eq!(parse_symbolic("go-w", 0o000), Ok(0o022));
+        assert_eq!(parse_symbolic("+x", 0o777), Ok(0o666));
+        assert_eq!(parse_symbolic("a=r", 0o022), Ok(0o333));
+        assert_eq!(parse_symbolic("u*r", 0o022), Err("`*': invalid symbolic mode operator".to_string()));
+        assert_eq!(parse_symbolic("a+q", 0o022), Err("`q': invalid symbolic mode character".to_string()));
+    }
+
+    #[test]
+    fn symbolic_output() {
+        assert_eq!(format_symbolic(0o022), "u=rwx,g=rx,o=rx");
+        assert_eq!(format_symbolic(0o077), "u=rwx,g=,o=");
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone)]
+pub enum Value {
+    Scalar(String),
+    Indexed(BTreeMap<usize, String>),
+    Associative(BTreeMap<String, String>),
+}
+
+impl Value {
+    /// The value seen through a plain `$name`, which for arrays is element 0.
+    pub fn scalar(&self) -> Option<String> {
+        match self {
+            Value::Scalar(value) => Some(value.clone()),
+            Value::Indexed(elements) => elements.get(&0).cloned(),
+            Value::Associative(elements) => elements.get("0").cloned(),
+        }
+    }
+
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Value::Scalar(value) => vec![value.clone()],
+            Value::Indexed(elements) => elements.values().cloned().collect(),
+            Value::Associative(elements) => elements.values().cloned().collect(),
+        }
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            Value::Scalar(_) => vec!["0".to_string()],
+            Value::Indexed(elements) => elements.keys().map(|key| key.to_string()).collect(),
+            Value::Associative(elements) => elements.keys().cloned().collect(),
+        }
+    }
+
+    /// Looks up one element; indexed subscripts must already be resolved to
+    /// a number by the caller.
+    pub fn element(&self, subscript: &str) -> Option<String> {
+        match self {
+            Value::Scalar(value) => (subscript == "0").then(|| value.clone()),
+            Value::Indexed(elements) => elements.get(&subscript.parse().ok()?).cloned(),
+            Value::Associative(elements) => elements.get(subscript).cloned(),
+        }
+    }
+
+    /// Resolves an indexed subscript, where negative numbers count back from
+    /// the end of the array.
+    pub fn resolve_index(&self, index: i64) -> Option<usize> {
+        if index >= 0 {
+            return Some(index as usize);
+        }
+        let len = match self {
+            Value::Indexed(elements) => elements.keys().next_back().map_or(0, |last| last + 1),
+            _ => 1,
+        };
+        usize::try_from(len as i64 + index).ok()
+    }
+}
+
+/// Long option names accepted by `set -o` / `set +o`.
+pub const SHELL_OPTIONS: &[&str] = &["allexport", "errexit", "noglob", "nounset", "pipefail", "verbose", "xtrace"];
+
+/// The single-letter `set` flags and the options they stand for.
+pub const OPTION_FLAGS: &[(char, &str)] = &[
+    ('a', "allexport"),
+    ('e', "errexit"),
+    ('f', "noglob"),
+    ('r', "restricted"),
+    ('u', "nounset"),
+    ('v', "verbose"),
+    ('x', "xtrace"),
+];
+
+/// Options only reachable through `shopt`.
+pub const SHOPT_OPTIONS: &[&str] = &["dotglob", "failglob", "nocaseglob", "nullglob"];
+
+pub struct Variable {
+    pub value: Value,
+    pub exported: bool,
+}
+
+/// State behind the special and dynamic parameters (`$?`, `$!`, `$_`,
+/// `$RANDOM`, `$SECONDS`, ...), which are computed rather than stored.
+pub struct ShellParameters {
+    pub shell_pid: u32,
+    /// The shell's parent when it started; subshells keep reporting it.
+    pub parent_pid: u32,
+    pub script_name: String,
+    pub positional: Vec<String>,
+
+    pub last_status: i32,
+    pub last_background_pid: Option<u32>,
+    pub last_argument: String,
+    pub line_number: usize,
+    pub interactive: bool,
+    pub job_control: bool,
+    pub input_flag: Option<char>,
+    pub source_depth: usize,
+    pub function_depth: usize,
+    /// Set while running a command whose status is being tested (`!`, or
+    /// the left of `&&` / `||`), where `set -e` doesn't apply.
+    pub condition_depth: usize,
+    pub options: BTreeSet<&'static str>,
+    /// An expansion failed in a way that ends a non-interactive shell, such
+    /// as `${name?}` or an unset variable under `set -u`.
+    pub fatal_error: bool,
+
+    seconds_base: u64,
+    seconds_start: Instant,
+    random_seed: u32,
+}
+
+impl ShellParameters {
+    pub fn new(script_name: String) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let shell_pid = std::process::id();
+
+        ShellParameters {
+            shell_pid,
+            parent_pid: std::os::unix::process::parent_id(),
+            last_argument: script_name.clone(),
+            script_name,
+            positional: Vec::new(),
+
+            last_status: 0,
+            last_background_pid: None,
+            line_number: 0,
+            interactive: false,
+            job_control: false,
+            input_flag: None,
+            source_depth: 0,
+            function_depth: 0,
+            condition_depth: 0,
+            options: BTreeSet::new(),
+            fatal_error: false,
+
+            seconds_base: 0,
+            seconds_start: Instant::now(),
+            random_seed: now.subsec_nanos() ^ shell_pid,
+        }
+    }
+
+    /// Resolves special and dynamic parameters; `None` means the name is an
+    /// ordinary variable (or an unset positional parameter).
+    pub fn lookup(&mut self, name: &str) -> Option<String> {
+        match name {
+            "$" => Some(self.shell_pid.to_string()),
+            "!" => Some(self.last_background_pid.map(|pid| pid.to_string()).unwrap_or_default()),
+            "#" => Some(self.positional.len().to_string()),
+            "@" | "*" => Some(self.positional.join(" ")),
+            "?" => Some(self.last_status.to_string()),
+            "-" => Some(self.flags()),
+            "_" => Some(self.last_argument.clone()),
+            "0" => Some(self.script_name.clone()),
+            "RANDOM" => Some(self.next_random().to_string()),
+            "SECONDS" => Some((self.seconds_base + self.seconds_start.elapsed().as_secs()).to_string()),
+            "LINENO" => Some(self.line_number.to_string()),
+            "PPID" => Some(self.parent_pid.to_string()),
+            "EPOCHREALTIME" => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                Some(format!("{}.{:06}", now.as_secs(), now.subsec_micros()))
+            }
+            _ => {
+                let index = name.parse::<usize>().ok().filter(|&i| i > 0)?;
+                self.positional.get(index - 1).cloned()
+            }
+        }
+    }
+
+    /// Handles assignments to dynamic parameters. Returns `Ok(false)` when the
+    /// name is an ordinary variable that should be stored as usual.
+    pub fn assign(&mut self, name: &str, value: &str) -> Result<bool, String> {
+        if self.is_readonly(name) {
+            return Err(format!("{}: readonly variable", name));
+        }
+        match name {
+            "RANDOM" => {
+                self.random_seed = value.parse().unwrap_or(0);
+                Ok(true)
+            }
+            "SECONDS" => {
+                self.seconds_base = value.parse().unwrap_or(0);
+                self.seconds_start = Instant::now();
+                Ok(true)
+            }
+            "LINENO" | "EPOCHREALTIME" => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// `PPID` can never be assigned, and a restricted shell also keeps the
+    /// `PATH`, `SHELL` and `ENV` it started with.
+    pub fn is_readonly(&self, name: &str) -> bool {
+        match name {
+            "PPID" => true,
+            "PATH" | "SHELL" | "ENV" => self.options.contains("restricted"),
+            _ => false,
+        }
+    }
+
+    fn flags(&self) -> String {
+        let mut flags: Vec<char> = OPTION_FLAGS.iter()
+            .filter(|(_, name)| self.options.contains(name))
+            .map(|(flag, _)| *flag)
+            .collect();
+        if self.interactive {
+            flags.push('i');
+        }
+        if self.job_control {
+            flags.push('m');
+        }
+        flags.sort();
+        flags.extend(self.input_flag);
+        flags.into_iter().collect()
+    }
+
+    fn next_random(&mut self) -> u32 {
+        self.random_seed = self.random_seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (self.random_seed >> 16) & 0x7fff
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{Read, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+use super::{Command, ShellStatus, CommandRegistry};
+use super::executor::status_code;
+use super::jobs::JobState;
+use super::signals::take_interrupt;
+
+pub struct WaitCommand;
+
+impl Command for WaitCommand {
+    fn execute(&self, args: &[String], registry: &CommandRegistry, _: &mut dyn Read, _: &mut dyn Write, error: &mut dyn Write) -> Result<ShellStatus, String> {
+        if args.is_empty() {
+            let ids = registry.jobs().ids();
+            for id in ids {
+                if let Err(status) = wait_for_job(registry, id) {
+                    return Ok(ShellStatus::Status(status));
+                }
+            }
+            return Ok(ShellStatus::Status(0));
+        }
+
+        // An unknown job or process gives 127; the remaining operands are
+        // still waited for.
+        let mut status = 0;
+        for arg in args {
+            let job = if arg.starts_with('%') {
+                registry.jobs().resolve(arg).map_err(|e| (e, 127))
+            } else if let Ok(pid) = arg.parse() {
+                match registry.jobs().find_pid(pid) {
+                    Some(id) => Ok(id),
+                    None => match wait_for_pid(pid) {
+                        Ok(code) => {
+                            status = code;
+                            continue;
+                        }
+                        Err(e) => Err((e, 127)),
+                    },
+                }
+            } else {
+                Err((format!("`{}': not a pid or valid job spec", arg), 1))
+            };
+
+            match job {
+                Ok(id) => match wait_for_job(registry, id) {
+                    Ok(code) => status = code,
+                    Err(interrupted) => return Ok(ShellStatus::Status(interrupted)),
+                },
+                Err((message, code)) => {
+                    writeln!(error, "wait: {}", message).map_err(|e| e.to_string())?;
+                    status = code;
+                }
+            }
+        }
+
+        Ok(ShellStatus::Status(status))
+    }
+
+    fn get_name(&self) -> &str {
+        "wait"
+    }
+}
+
+/// Waits for a job without giving it the terminal. Finished jobs leave the
+/// table without being reported again. `Err` carries the `128+signal`
+/// status of a wait cut short by Ctrl-C or a trapped signal.
+fn wait_for_job(registry: &CommandRegistry, id: usize) -> Result<i32, i32> {
+    let Some(mut job) = registry.jobs().remove(id) else {
+        return Ok(127);
+    };
+
+    let interrupted = job.wait(true);
+    let status = match (job.state(), interrupted) {
+        (JobState::Done(code), _) => return Ok(code),
+        (_, Some(signal)) => Err(128 + signal),
+        _ => Ok(128 + libc::SIGTSTP),
+    };
+    registry.jobs().insert(job);
+    status
+}
+
+fn wait_for_pid(pid: i32) -> Result<i32, String> {
+    let mut raw = 0;
+    while unsafe { libc::waitpid(pid, &mut raw, 0) } < 0 {
+        if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return Err(format!("pid {} is not a child of this shell", pid));
+        }
+        if let Some(signal) = take_interrupt() {
+            return Ok(128 + signal);
+        }
+    }
+    Ok(status_code(ExitStatus::from_raw(raw)))
+}
@@ -1,50 +1,220 @@
-mod commands; 
-mod utils;    
+mod commands;
+mod utils;
 mod ui;
 
 use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process;
 
 use commands::{CommandRegistry, ShellStatus, ShellExecutor};
 use ui::ShellHelper;
+use utils::{DumpMode, ScriptSource, ShellOption, StartupFiles};
 use rustyline::{CompletionType, Config, EditMode, Editor, error::ReadlineError};
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let shell_args = match utils::parse_shell_args(&args, std::io::stdin().is_terminal()) {
+        Ok(shell_args) => shell_args,
+        Err(e) => {
+            eprintln!("rust-shell: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if let Some(mode) = &shell_args.dump {
+        dump_script(&shell_args.source, mode);
+    }
+
     let registry = CommandRegistry::default();
-    let command_names = registry.get_command_names();
-    let helper = ShellHelper::new(command_names);
     let executor = ShellExecutor::new(&registry);
 
-    let path_hist = env::var("HISTFILE").unwrap_or_default();
-    if path_hist != "" {
-        let _ = registry.load_history_from_file(&path_hist);
+    if let Some(name) = &shell_args.script_name {
+        registry.set_script_name(name);
+    }
+    registry.set_positional_params(shell_args.positional);
+    for option in &shell_args.options {
+        let result = match option {
+            ShellOption::Flag(flag) => registry.set_option_flag(*flag, true),
+            ShellOption::Name(name) => registry.set_option(name, true),
+        };
+        if let Err(e) = result {
+            eprintln!("rust-shell: {}", e);
+            process::exit(2);
+        }
+    }
+
+    let interactive = matches!(shell_args.source, ScriptSource::Interactive);
+    if interactive {
+        registry.set_interactive(true);
+        registry.set_input_flag('s');
+        registry.set_job_control(commands::init_job_control());
+        commands::init_interactive_signals();
+    }
+
+    let arg0 = env::args().next().unwrap_or_default();
+    let mut startup = shell_args.startup;
+    startup.login |= arg0.starts_with('-');
+    run_startup_files(&registry, &startup, interactive);
+
+    // The startup files set things up before the restrictions apply.
+    let invoked_as_restricted = Path::new(arg0.trim_start_matches('-')).file_name().is_some_and(|name| name == "rrust-shell");
+    if shell_args.restricted || invoked_as_restricted {
+        let _ = registry.set_option_flag('r', true);
+    }
+
+    let status = match shell_args.source {
+        ScriptSource::Interactive => run_interactive(&registry, &executor),
+        ScriptSource::Command(command) => {
+            registry.set_input_flag('c');
+            executor.run_script(&command)
+        }
+        ScriptSource::File(path) => match fs::read_to_string(&path) {
+            Ok(source) => executor.run_script(&source),
+            Err(e) => {
+                eprintln!("rust-shell: {}: {}", path, e);
+                process::exit(127);
+            }
+        },
+        ScriptSource::Stdin => {
+            registry.set_input_flag('s');
+            run_stdin(&registry, &executor)
+        }
+    };
+
+    let code = match status {
+        ShellStatus::Exit(code) => code,
+        _ => executor.run_exit_trap(registry.get_last_status()),
+    };
+    process::exit(code);
+}
+
+/// Prints the parsed script (or its re-formatted source) without running it.
+fn dump_script(source: &ScriptSource, mode: &DumpMode) -> ! {
+    let text = match source {
+        ScriptSource::Command(command) => command.clone(),
+        ScriptSource::File(path) => fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("rust-shell: {}: {}", path, e);
+            process::exit(127);
+        }),
+        ScriptSource::Interactive | ScriptSource::Stdin => {
+            let mut text = String::new();
+            while let Some(line) = utils::read_stdin_line() {
+                text.push_str(&line);
+                text.push('\n');
+            }
+            text
+        }
+    };
+
+    let dump = match mode {
+        DumpMode::Ast(format) => utils::dump_ast(&text, *format),
+        DumpMode::Format => utils::format_source(&text),
+    };
+    match dump {
+        Ok(dump) => print!("{}", dump),
+        Err(e) => {
+            eprintln!("rust-shell: {}", e);
+            process::exit(2);
+        }
+    }
+    process::exit(0);
+}
+
+/// Login shells read `/etc/profile` and `~/.rustshell_profile`; other
+/// interactive shells read `~/.rustshellrc` (or the `--rcfile` given).
+fn run_startup_files(registry: &CommandRegistry, startup: &StartupFiles, interactive: bool) {
+    let home = registry.get_variable("HOME").unwrap_or_default();
+
+    let files = if startup.login {
+        if startup.no_profile {
+            return;
+        }
+        vec!["/etc/profile".to_string(), format!("{}/.rustshell_profile", home)]
+    } else if interactive && !startup.no_rc {
+        vec![startup.rc_file.clone().unwrap_or_else(|| format!("{}/.rustshellrc", home))]
+    } else {
+        return;
+    };
+
+    let Some(source) = registry.get_builtin("source") else {
+        return;
+    };
+
+    for file in files.iter().filter(|file| Path::new(file).is_file()) {
+        match source.execute(std::slice::from_ref(file), registry, &mut utils::RawStdin, &mut io::stdout(), &mut io::stderr()) {
+            Ok(ShellStatus::Exit(code)) => process::exit(code),
+            Ok(_) => {}
+            Err(e) => eprintln!("{}", e),
+        }
     }
+}
+
+fn run_stdin(registry: &CommandRegistry, executor: &ShellExecutor) -> ShellStatus {
+    let mut line_number = 0;
+
+    while let Some(mut line) = utils::read_stdin_line() {
+        line_number += 1;
+        registry.set_line_number(line_number);
+
+        // A function definition may continue over several lines.
+        while utils::is_incomplete(&line) && let Some(next) = utils::read_stdin_line() {
+            line_number += 1;
+            line = format!("{}\n{}", line, next);
+        }
+
+        if let ShellStatus::Exit(code) = executor.run_line(&line) {
+            return ShellStatus::Exit(code);
+        }
+    }
+
+    ShellStatus::Continue
+}
 
+fn run_interactive(registry: &CommandRegistry, executor: &ShellExecutor) -> ShellStatus {
+    let helper = ShellHelper::new(Vec::new());
+
+    let path_hist = registry.get_variable("HISTFILE").unwrap_or_default();
+    if !path_hist.is_empty() {
+        let _ = registry.load_history_from_file(&path_hist);
+    }
     let config = Config::builder()
         .completion_type(CompletionType::List)
-        .edit_mode(EditMode::Emacs) 
+        .edit_mode(EditMode::Emacs)
         .build();
 
-    let mut editor = 
+    let mut editor =
         Editor::<ShellHelper, _>::with_config(config).unwrap();
     editor.set_helper(Some(helper));
 
+    let mut line_number = 0;
+
     loop {
-        let readline = editor.readline("$ ");
+        if let ShellStatus::Exit(code) = executor.run_pending_traps() {
+            return ShellStatus::Exit(code);
+        }
+
+        for report in registry.jobs().take_finished() {
+            eprintln!("{}", report);
+        }
+
+        // Picks up a changed PATH or a `hash -r`.
+        if let Some(helper) = editor.helper_mut() {
+            helper.commands = registry.get_command_names();
+        }
+
+        let prompt = registry.get_variable("PS1").unwrap_or_else(|| "$ ".to_string());
+        let readline = editor.readline(&prompt);
         match readline {
             Ok(line) => {
+                line_number += 1;
+                registry.set_line_number(line_number);
                 registry.add_history_entry(&line);
                 editor.add_history_entry(line.as_str()).ok();
 
-                let commands = utils::parse_input(line.as_str());
-                
-                if commands.is_empty() {
-                    continue;
-                }
-
-                match executor.run(&commands) {
-                    Ok(ShellStatus::Exit) => break,
-                    Ok(ShellStatus::Continue) => continue,
-                    Err(e) => eprintln!("{}", e),
+                if let ShellStatus::Exit(code) = executor.run_line(&line) {
+                    return ShellStatus::Exit(code);
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -53,12 +223,12 @@ fn main() {
             }
             Err(ReadlineError::Eof) => {
                 println!("exit");
-                break;
+                return ShellStatus::Continue;
             }
             Err(err) => {
                 eprintln!("Error: {:?}", err);
-                break;
+                return ShellStatus::Exit(1);
             }
         }
     }
-}
\ No newline at end of file
+}
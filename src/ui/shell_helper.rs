@@ -4,6 +4,8 @@ use rustyline::highlight::Highlighter;
 use rustyline::validate::Validator;
 use rustyline::{Context, Helper, Result};
 
+use crate::utils::is_incomplete;
+
 pub struct ShellHelper {
     pub commands: Vec<String>,
 }
@@ -70,7 +72,11 @@ impl Hinter for ShellHelper {
 impl Highlighter for ShellHelper {}
 
 impl Validator for ShellHelper {
-    fn validate(&self, _ctx: &mut rustyline::validate::ValidationContext) -> Result<rustyline::validate::ValidationResult> {
+    fn validate(&self, ctx: &mut rustyline::validate::ValidationContext) -> Result<rustyline::validate::ValidationResult> {
+        // Keeps reading lines until a function definition is closed.
+        if is_incomplete(ctx.input()) {
+            return Ok(rustyline::validate::ValidationResult::Incomplete);
+        }
         Ok(rustyline::validate::ValidationResult::Valid(None))
     }
 }
\ No newline at end of file
@@ -0,0 +1,565 @@
+use super::parser::{
+    AndOr, ArrayElement, Assignment, AssignmentValue, FdRedirect, Parameter, ParameterForm, ParameterOp, ParsedCommand,
+    Pipeline, ReplaceMode, Subscript, TimeFormat, Word, WordPart, logical_lines, parse_input,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AstFormat {
+    Text,
+    Json,
+}
+
+/// A generic tree built from the parsed script, so the text and JSON dumps
+/// share one walk over the syntax types.
+struct Node {
+    kind: &'static str,
+    attributes: Vec<(&'static str, Attribute)>,
+    children: Vec<Node>,
+}
+
+enum Attribute {
+    Text(String),
+    Number(usize),
+    Flag(bool),
+}
+
+impl Node {
+    fn new(kind: &'static str) -> Self {
+        Node { kind, attributes: Vec::new(), children: Vec::new() }
+    }
+
+    fn text(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.attributes.push((name, Attribute::Text(value.into())));
+        self
+    }
+
+    fn number(mut self, name: &'static str, value: usize) -> Self {
+        self.attributes.push((name, Attribute::Number(value)));
+        self
+    }
+
+    fn flag(mut self, name: &'static str, value: bool) -> Self {
+        self.attributes.push((name, Attribute::Flag(value)));
+        self
+    }
+
+    fn child(mut self, child: Node) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Wraps a word in a node naming its role, e.g. `Pattern` or `Offset`.
+    fn role(kind: &'static str, word: &Word) -> Node {
+        Node::new(kind).child(word_node(word))
+    }
+}
+
+/// Parses `source` line by line, as the executor does, keeping the line
+/// number of every pipeline. Fails at the first syntax error.
+fn parse_lines(source: &str) -> Result<Vec<(usize, Vec<Pipeline>)>, String> {
+    let mut lines = Vec::new();
+    for (line, text) in logical_lines(source) {
+        let pipelines = parse_input(&text).map_err(|e| format!("line {}: {}", line, e))?;
+        if !pipelines.is_empty() {
+            lines.push((line, pipelines));
+        }
+    }
+    Ok(lines)
+}
+
+/// Prints the syntax tree of `source` without running it.
+pub fn dump_ast(source: &str, format: AstFormat) -> Result<String, String> {
+    let mut script = Node::new("Script");
+    for (line, pipelines) in parse_lines(source)? {
+        for pipeline in &pipelines {
+            script.children.push(pipeline_node(pipeline, line));
+        }
+    }
+
+    let mut output = String::new();
+    match format {
+        AstFormat::Text => write_text(&script, 0, &mut output),
+        AstFormat::Json => {
+            write_json(&script, 0, &mut output);
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+fn pipeline_node(pipeline: &Pipeline, line: usize) -> Node {
+    let mut node = Node::new("Pipeline").number("line", line).flag("background", pipeline.background);
+    match pipeline.time {
+        Some(TimeFormat::Variable) => node = node.text("time", "TIMEFORMAT"),
+        Some(TimeFormat::Posix) => node = node.text("time", "posix"),
+        None => {}
+    }
+    if pipeline.negated {
+        node = node.flag("negated", true);
+    }
+    match pipeline.and_or {
+        Some(AndOr::And) => node = node.text("run_if", "&&"),
+        Some(AndOr::Or) => node = node.text("run_if", "||"),
+        None => {}
+    }
+    node.children.extend(pipeline.commands.iter().map(|command| command_node(command, line)));
+    node
+}
+
+fn command_node(command: &ParsedCommand, line: usize) -> Node {
+    if let Some(function) = &command.function {
+        let mut node = Node::new("Function").text("name", &function.name);
+        node.children.extend(function.body.iter().map(|pipeline| pipeline_node(pipeline, line)));
+        return node;
+    }
+
+    let mut node = Node::new("Command");
+    node.children.extend(command.assignments.iter().map(assignment_node));
+    node.children.extend(command.words.iter().map(word_node));
+
+    for redirect in &command.redirects {
+        node.children.push(match redirect {
+            FdRedirect::Open { fd, operator, path } => {
+                Node::new("Redirect").number("fd", *fd as usize).text("operator", *operator).child(word_node(path))
+            }
+            FdRedirect::Duplicate { fd, target } => {
+                Node::new("Duplicate").number("fd", *fd as usize).number("target", *target as usize)
+            }
+            FdRedirect::Close { fd } => Node::new("Close").number("fd", *fd as usize),
+        });
+    }
+
+    node
+}
+
+fn assignment_node(assignment: &Assignment) -> Node {
+    let mut node = Node::new("Assignment").text("name", &assignment.name);
+    if let Some(subscript) = &assignment.subscript {
+        node = node.child(Node::role("Subscript", subscript));
+    }
+
+    match &assignment.value {
+        AssignmentValue::Scalar(value) => node.child(Node::role("Value", value)),
+        AssignmentValue::Array(elements) => {
+            let mut array = Node::new("Array");
+            for ArrayElement { key, value } in elements {
+                let mut element = Node::new("Element");
+                if let Some(key) = key {
+                    element = element.child(Node::role("Key", key));
+                }
+                array.children.push(element.child(Node::role("Value", value)));
+            }
+            node.child(array)
+        }
+    }
+}
+
+fn word_node(word: &Word) -> Node {
+    let mut node = Node::new("Word");
+    for part in &word.parts {
+        node.children.push(match part {
+            WordPart::Literal(text) => Node::new("Literal").text("text", text),
+            WordPart::Quoted(text) => Node::new("Quoted").text("text", text),
+            WordPart::Parameter(parameter) => parameter_node(parameter),
+            WordPart::Command { source, quoted } => {
+                Node::new("CommandSubstitution").text("source", source).flag("quoted", *quoted)
+            }
+        });
+    }
+    node
+}
+
+fn parameter_node(parameter: &Parameter) -> Node {
+    let form = match parameter.form {
+        ParameterForm::Value => "value",
+        ParameterForm::Length => "length",
+        ParameterForm::Keys => "keys",
+        ParameterForm::Names => "names",
+    };
+    let mut node = Node::new("Parameter")
+        .text("name", &parameter.name)
+        .text("form", form)
+        .flag("quoted", parameter.quoted);
+
+    match &parameter.subscript {
+        Some(Subscript::All) => node = node.text("subscript", "@"),
+        Some(Subscript::Star) => node = node.text("subscript", "*"),
+        Some(Subscript::Index(index)) => node = node.child(Node::role("Subscript", index)),
+        None => {}
+    }
+
+    if let Some(op) = &parameter.op {
+        let mut operator = Node::new("Operator").text("symbol", operator_symbol(op));
+        match op {
+            ParameterOp::Default { word, .. }
+            | ParameterOp::Assign { word, .. }
+            | ParameterOp::Error { word, .. }
+            | ParameterOp::Alternative { word, .. } => operator = operator.child(word_node(word)),
+            ParameterOp::RemovePrefix { pattern, .. } | ParameterOp::RemoveSuffix { pattern, .. } => {
+                operator = operator.child(Node::role("Pattern", pattern));
+            }
+            ParameterOp::Replace { pattern, replacement, .. } => {
+                operator = operator.child(Node::role("Pattern", pattern)).child(Node::role("Replacement", replacement));
+            }
+            ParameterOp::Substring { offset, length } => {
+                operator = operator.child(Node::role("Offset", offset));
+                if let Some(length) = length {
+                    operator = operator.child(Node::role("Length", length));
+                }
+            }
+            ParameterOp::Case { .. } => {}
+        }
+        node = node.child(operator);
+    }
+
+    node
+}
+
+fn operator_symbol(op: &ParameterOp) -> String {
+    let colon = |check_null: bool| if check_null { ":" } else { "" };
+
+    match op {
+        ParameterOp::Default { check_null, .. } => format!("{}-", colon(*check_null)),
+        ParameterOp::Assign { check_null, .. } => format!("{}=", colon(*check_null)),
+        ParameterOp::Error { check_null, .. } => format!("{}?", colon(*check_null)),
+        ParameterOp::Alternative { check_null, .. } => format!("{}+", colon(*check_null)),
+        ParameterOp::RemovePrefix { longest, .. } => if *longest { "##" } else { "#" }.to_string(),
+        ParameterOp::RemoveSuffix { longest, .. } => if *longest { "%%" } else { "%" }.to_string(),
+        ParameterOp::Replace { mode, .. } => match mode {
+            ReplaceMode::First => "/",
+            ReplaceMode::All => "//",
+            ReplaceMode::Prefix => "/#",
+            ReplaceMode::Suffix => "/%",
+        }.to_string(),
+        ParameterOp::Substring { .. } => ":".to_string(),
+        ParameterOp::Case { upper, all } => match (upper, all) {
+            (true, true) => "^^",
+            (true, false) => "^",
+            (false, true) => ",,",
+            (false, false) => ",",
+        }.to_string(),
+    }
+}
+
+fn write_text(node: &Node, depth: usize, output: &mut String) {
+    output.push_str(&"  ".repeat(depth));
+    output.push_str(node.kind);
+    for (name, value) in &node.attributes {
+        match value {
+            Attribute::Text(text) => output.push_str(&format!(" {}={:?}", name, text)),
+            Attribute::Number(number) => output.push_str(&format!(" {}={}", name, number)),
+            Attribute::Flag(true) => output.push_str(&format!(" {}", name)),
+            Attribute::Flag(false) => {}
+        }
+    }
+    output.push('\n');
+
+    for child in &node.children {
+        write_text(child, depth + 1, output);
+    }
+}
+
+fn write_json(node: &Node, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth + 1);
+    output.push_str("{\n");
+    output.push_str(&format!("{}\"type\": {}", indent, json_string(node.kind)));
+
+    for (name, value) in &node.attributes {
+        let value = match value {
+            Attribute::Text(text) => json_string(text),
+            Attribute::Number(number) => number.to_string(),
+            Attribute::Flag(flag) => flag.to_string(),
+        };
+        output.push_str(&format!(",\n{}\"{}\": {}", indent, name, value));
+    }
+
+    if !node.children.is_empty() {
+        output.push_str(&format!(",\n{}\"children\": [", indent));
+        for (i, child) in node.children.iter().enumerate() {
+            output.push_str(if i == 0 { "\n" } else { ",\n" });
+            output.push_str(&"  ".repeat(depth + 2));
+            write_json(child, depth + 2, output);
+        }
+        output.push_str(&format!("\n{}]", indent));
+    }
+
+    output.push_str(&format!("\n{}}}", "  ".repeat(depth)));
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Re-emits `source` from its syntax tree: one line per source line with
+/// pipelines separated by `; ` (or ending in ` &`), quoted text in double quotes and every
+/// parameter in braces. Comments and blank lines are dropped.
+pub fn format_source(source: &str) -> Result<String, String> {
+    let mut output = String::new();
+    for (_, pipelines) in parse_lines(source)? {
+        let statements = statements(&pipelines);
+        let line: Vec<String> = statements.iter().enumerate()
+            .map(|(index, (text, background))| match (background, index + 1 == statements.len()) {
+                (true, _) => format!("{} &", text),
+                (false, true) => text.clone(),
+                (false, false) => format!("{};", text),
+            })
+            .collect();
+        output.push_str(&line.join(" "));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// The command text of a pipeline, also used to describe background jobs.
+pub fn format_pipeline(pipeline: &Pipeline) -> String {
+    let commands: Vec<String> = pipeline.commands.iter().map(format_command).collect();
+    let keyword = match pipeline.time {
+        Some(TimeFormat::Variable) => "time ",
+        Some(TimeFormat::Posix) => "time -p ",
+        None => "",
+    };
+    let negation = if pipeline.negated { "! " } else { "" };
+    format!("{}{}{}", keyword, negation, commands.join(" | ")).trim_end().to_string()
+}
+
+/// Joins pipelines linked by `&&` and `||` into one statement each, such as
+/// `a && b || c`, noting whether the statement ends in `&`.
+fn statements(pipelines: &[Pipeline]) -> Vec<(String, bool)> {
+    let mut statements: Vec<(String, bool)> = Vec::new();
+    for pipeline in pipelines {
+        let text = format_pipeline(pipeline);
+        match (pipeline.and_or, statements.last_mut()) {
+            (Some(and_or), Some((statement, background))) => {
+                let operator = if and_or == AndOr::And { "&&" } else { "||" };
+                *statement = format!("{} {} {}", statement, operator, text);
+                *background = pipeline.background;
+            }
+            _ => statements.push((text, pipeline.background)),
+        }
+    }
+    statements
+}
+
+/// A function as `type` shows it: the body on its own lines, one
+/// pipeline per line.
+pub fn format_function(name: &str, body: &[Pipeline]) -> String {
+    let mut text = format!("{} () \n{{ \n", name);
+    let statements = statements(body);
+    for (index, (statement, background)) in statements.iter().enumerate() {
+        let separator = match (background, index + 1 == statements.len()) {
+            (true, _) => " &",
+            (false, true) => "",
+            (false, false) => ";",
+        };
+        text.push_str(&format!("    {}{}\n", statement, separator));
+    }
+    text.push('}');
+    text
+}
+
+
+fn format_command(command: &ParsedCommand) -> String {
+    if let Some(function) = &command.function {
+        let body: Vec<String> = statements(&function.body).into_iter()
+            .map(|(statement, background)| format!("{}{}", statement, if background { " &" } else { ";" }))
+            .collect();
+        return format!("{} () {{ {} }}", function.name, body.join(" "));
+    }
+
+    let mut pieces: Vec<String> = command.assignments.iter().map(format_assignment).collect();
+    pieces.extend(command.words.iter().map(format_word));
+
+    for redirect in &command.redirects {
+        pieces.push(match redirect {
+            FdRedirect::Open { fd, operator, path } => format!("{}{} {}", default_fd(*fd, operator), operator, format_word(path)),
+            FdRedirect::Duplicate { fd, target } => format!("{}>&{}", default_fd(*fd, ">"), target),
+            FdRedirect::Close { fd } => format!("{}>&-", default_fd(*fd, ">")),
+        });
+    }
+
+    pieces.join(" ")
+}
+
+/// The descriptor number to write before `operator`, left out when it's
+/// the one the operator implies (`<` reads fd 0, the others write fd 1).
+fn default_fd(fd: i32, operator: &str) -> String {
+    match (fd, operator.starts_with('<')) {
+        (0, true) | (1, false) => String::new(),
+        _ => fd.to_string(),
+    }
+}
+
+fn format_assignment(assignment: &Assignment) -> String {
+    let mut text = assignment.name.clone();
+    if let Some(subscript) = &assignment.subscript {
+        text.push_str(&format!("[{}]", format_word(subscript)));
+    }
+    text.push('=');
+
+    match &assignment.value {
+        AssignmentValue::Scalar(value) => text.push_str(&format_word(value)),
+        AssignmentValue::Array(elements) => {
+            let elements: Vec<String> = elements.iter()
+                .map(|ArrayElement { key, value }| match key {
+                    Some(key) => format!("[{}]={}", format_word(key), format_word(value)),
+                    None => format_word(value),
+                })
+                .collect();
+            text.push_str(&format!("({})", elements.join(" ")));
+        }
+    }
+
+    text
+}
+
+fn format_word(word: &Word) -> String {
+    format_parts(&word.parts, false, false)
+}
+
+/// Formats word parts, grouping runs of quoted parts into one double-quoted
+/// string. Inside `${...}` (`in_braces`) a closing brace must be escaped, and
+/// operands of a quoted parameter (`implicitly_quoted`) need no quotes.
+fn format_parts(parts: &[WordPart], in_braces: bool, implicitly_quoted: bool) -> String {
+    let mut output = String::new();
+    let mut in_double_quotes = false;
+
+    for part in parts {
+        let quoted = match part {
+            WordPart::Literal(_) => false,
+            WordPart::Quoted(_) => true,
+            WordPart::Parameter(parameter) => parameter.quoted,
+            WordPart::Command { quoted, .. } => *quoted,
+        };
+        if !implicitly_quoted && quoted != in_double_quotes {
+            output.push('"');
+            in_double_quotes = quoted;
+        }
+
+        match part {
+            WordPart::Literal(text) if in_braces => output.push_str(&text.replace('}', "\\}")),
+            WordPart::Literal(text) => output.push_str(text),
+            WordPart::Quoted(text) => {
+                for c in text.chars() {
+                    if matches!(c, '"' | '\\' | '$') || (in_braces && c == '}') {
+                        output.push('\\');
+                    }
+                    output.push(c);
+                }
+            }
+            WordPart::Parameter(parameter) => output.push_str(&format_parameter(parameter)),
+            WordPart::Command { source, .. } => output.push_str(&format!("$({})", source)),
+        }
+    }
+
+    if in_double_quotes {
+        output.push('"');
+    }
+    output
+}
+
+fn format_parameter(parameter: &Parameter) -> String {
+    let operand = |word: &Word| format_parts(&word.parts, true, parameter.quoted);
+
+    let mut text = String::from("${");
+    match parameter.form {
+        ParameterForm::Length => text.push('#'),
+        ParameterForm::Keys | ParameterForm::Names => text.push('!'),
+        ParameterForm::Value => {}
+    }
+    text.push_str(&parameter.name);
+
+    if parameter.form == ParameterForm::Names {
+        text.push(if parameter.subscript == Some(Subscript::Star) { '*' } else { '@' });
+        text.push('}');
+        return text;
+    }
+
+    match &parameter.subscript {
+        Some(Subscript::All) => text.push_str("[@]"),
+        Some(Subscript::Star) => text.push_str("[*]"),
+        Some(Subscript::Index(index)) => text.push_str(&format!("[{}]", format_parts(&index.parts, true, true))),
+        None => {}
+    }
+
+    if let Some(op) = &parameter.op {
+        text.push_str(&operator_symbol(op));
+        match op {
+            ParameterOp::Default { word, .. }
+            | ParameterOp::Assign { word, .. }
+            | ParameterOp::Error { word, .. }
+            | ParameterOp::Alternative { word, .. } => text.push_str(&operand(word)),
+            ParameterOp::RemovePrefix { pattern, .. } | ParameterOp::RemoveSuffix { pattern, .. } => {
+                text.push_str(&operand(pattern));
+            }
+            ParameterOp::Replace { pattern, replacement, .. } => {
+                text.push_str(&operand(pattern));
+                if !replacement.is_empty() {
+                    text.push('/');
+                    text.push_str(&operand(replacement));
+                }
+            }
+            ParameterOp::Substring { offset, length } => {
+                // `${x:-1}` would read as a default value, so keep a space.
+                let offset = operand(offset);
+                if offset.starts_with('-') {
+                    text.push(' ');
+                }
+                text.push_str(&offset);
+                if let Some(length) = length {
+                    text.push(':');
+                    text.push_str(&operand(length));
+                }
+            }
+            ParameterOp::Case { .. } => {}
+        }
+    }
+
+    text.push('}');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_normalises_spacing_and_quoting() {
+        assert_eq!(format_source("echo   hi|cat>o;ls&&pwd").unwrap(), "echo hi | cat > o; ls && pwd\n");
+        assert_eq!(format_source("echo 'it''s' $x").unwrap(), "echo \"its\" ${x}\n");
+        assert_eq!(format_source("x=1  y=\"a b\" cmd 2>&1 <in 3>>log 4>&-").unwrap(), "x=1 y=\"a b\" cmd 2>&1 < in 3>> log 4>&-\n");
+        assert_eq!(format_source("f() { echo  hi;  }; time -p ! ls | wc &").unwrap(), "f () { echo hi; }; time -p ! ls | wc &\n");
+        assert_eq!(format_source("! time -p ls").unwrap(), "time -p ! ls\n");
+    }
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn dump_shows_redirections_in_order() {
+        let dump = dump_ast("ls 2>&1 >out", AstFormat::Text).unwrap();
+        let duplicate = dump.find("Duplicate fd=2 target=1").unwrap();
+        let redirect = dump.find("Redirect fd=1 operator=\">\"").unwrap();
+        assert!(duplicate < redirect);
+    }
+
+    #[test]
+    fn syntax_errors_name_their_line() {
+        assert_eq!(format_source("echo ok\necho a | | b"), Err("line 2: Syntax error: unexpected `|'".to_string()));
+        assert!(dump_ast("echo \"open", AstFormat::Json).is_err());
+    }
+}
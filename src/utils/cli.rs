@@ -0,0 +1,168 @@
+use super::ast::AstFormat;
+
+pub enum ScriptSource {
+    Interactive,
+    Stdin,
+    Command(String),
+    File(String),
+}
+
+#[derive(Default)]
+pub struct StartupFiles {
+    pub login: bool,
+    pub no_rc: bool,
+    pub no_profile: bool,
+    pub rc_file: Option<String>,
+}
+
+/// `-n` / `--dump-ast[=text|json]` and `--format` parse the input and print
+/// it back instead of running it.
+pub enum DumpMode {
+    Ast(AstFormat),
+    Format,
+}
+
+pub struct ShellArgs {
+    pub source: ScriptSource,
+    pub dump: Option<DumpMode>,
+    pub script_name: Option<String>,
+    pub positional: Vec<String>,
+    pub startup: StartupFiles,
+    /// Options turned on with `-e`, `-x`, ... or `-o name`, by long name
+    /// for `-o` and by letter otherwise.
+    pub options: Vec<ShellOption>,
+    /// `-r`, applied only once the startup files have run.
+    pub restricted: bool,
+}
+
+pub enum ShellOption {
+    Flag(char),
+    Name(String),
+}
+
+/// Parses the shell's own command line:
+/// `rust-shell [-aefilnrsuvx] [-o option] [--login] [--norc] [--noprofile] [--rcfile file]
+/// [--dump-ast[=text|json]] [--format] [-c command [name [args...]]] [script [args...]]`.
+pub fn parse_shell_args(args: &[String], stdin_is_tty: bool) -> Result<ShellArgs, String> {
+    let mut command_mode = false;
+    let mut read_stdin = false;
+    let mut startup = StartupFiles::default();
+    let mut dump = None;
+    let mut options = Vec::new();
+    let mut restricted = false;
+
+    let mut index = 0;
+    while let Some(arg) = args.get(index) {
+        index += 1;
+
+        match arg.as_str() {
+            "--" | "-" => break,
+            "--login" => startup.login = true,
+            "--norc" => startup.no_rc = true,
+            "--noprofile" => startup.no_profile = true,
+            "--dump-ast" | "--dump-ast=text" => dump = Some(DumpMode::Ast(AstFormat::Text)),
+            "--dump-ast=json" => dump = Some(DumpMode::Ast(AstFormat::Json)),
+            "--format" => dump = Some(DumpMode::Format),
+            "-o" => {
+                let name = args.get(index).ok_or("-o: option requires an argument")?;
+                options.push(ShellOption::Name(name.clone()));
+                index += 1;
+            }
+            "--rcfile" => {
+                let file = args.get(index).ok_or("--rcfile: option requires an argument")?;
+                startup.rc_file = Some(file.clone());
+                index += 1;
+            }
+            long if long.starts_with("--dump-ast=") => {
+                return Err(format!("{}: format must be `text' or `json'", long));
+            }
+            long if long.starts_with("--") => return Err(format!("{}: invalid option", long)),
+            flags if flags.starts_with('-') => {
+                for flag in flags.chars().skip(1) {
+                    match flag {
+                        'c' => command_mode = true,
+                        's' => read_stdin = true,
+                        'l' => startup.login = true,
+                        'n' => dump = Some(DumpMode::Ast(AstFormat::Text)),
+                        'r' => restricted = true,
+                        'a' | 'e' | 'f' | 'u' | 'v' | 'x' => options.push(ShellOption::Flag(flag)),
+                        _ => return Err(format!("-{}: invalid option", flag)),
+                    }
+                }
+            }
+            _ => {
+                index -= 1;
+                break;
+            }
+        }
+    }
+
+    let mut operands = args[index..].iter().cloned();
+
+    if command_mode {
+        let command = operands.next().ok_or("-c: option requires an argument")?;
+        return Ok(ShellArgs {
+            source: ScriptSource::Command(command),
+            dump,
+            script_name: operands.next(),
+            positional: operands.collect(),
+            startup,
+            options,
+            restricted,
+        });
+    }
+
+    if read_stdin || args.len() == index {
+        let source = if stdin_is_tty { ScriptSource::Interactive } else { ScriptSource::Stdin };
+        return Ok(ShellArgs { source, dump, script_name: None, positional: operands.collect(), startup, options, restricted });
+    }
+
+    let script = operands.next().unwrap_or_default();
+    Ok(ShellArgs {
+        source: ScriptSource::File(script.clone()),
+        dump,
+        script_name: Some(script),
+        positional: operands.collect(),
+        startup,
+        options,
+        restricted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<ShellArgs, String> {
+        parse_shell_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>(), false)
+    }
+
+    #[test]
+    fn startup_switches() {
+        let args = parse(&["--norc", "--noprofile", "--rcfile", "team.rc", "-l"]).unwrap();
+        assert!(args.startup.login && args.startup.no_rc && args.startup.no_profile);
+        assert_eq!(args.startup.rc_file.as_deref(), Some("team.rc"));
+        assert!(matches!(args.source, ScriptSource::Stdin));
+        assert!(parse(&["--rcfile"]).is_err());
+    }
+
+    #[test]
+    fn command_strings_and_scripts_take_their_operands() {
+        let args = parse(&["-c", "echo hi", "name", "a", "b"]).unwrap();
+        assert!(matches!(args.source, ScriptSource::Command(ref command) if command == "echo hi"));
+        assert_eq!(args.script_name.as_deref(), Some("name"));
+        assert_eq!(args.positional, ["a", "b"]);
+
+        let args = parse(&["-e", "script.sh", "-x"]).unwrap();
+        assert!(matches!(args.source, ScriptSource::File(ref path) if path == "script.sh"));
+        assert_eq!(args.positional, ["-x"]);
+        assert!(matches!(args.options.as_slice(), [ShellOption::Flag('e')]));
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert_eq!(parse(&["--bogus"]).err().as_deref(), Some("--bogus: invalid option"));
+        assert_eq!(parse(&["-q"]).err().as_deref(), Some("-q: invalid option"));
+        assert!(parse(&["-c"]).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::mem::ManuallyDrop;
+use std::os::fd::FromRawFd;
 
 pub fn open_file(path: &str, append: bool) -> Result<File, String> {
         OpenOptions::new()
@@ -8,4 +11,50 @@ pub fn open_file(path: &str, append: bool) -> Result<File, String> {
             .append(append)
             .open(path)
             .map_err(|e| format!("Failed to open {}: {}", path, e))
-}
\ No newline at end of file
+}
+
+/// Opens the file named by a `<` redirection.
+pub fn open_input_file(path: &str) -> Result<File, String> {
+    File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))
+}
+
+/// Unbuffered standard input: every `read` goes straight to fd 0, so a
+/// builtin never swallows input meant for the shell or for later commands.
+pub struct RawStdin;
+
+impl Read for RawStdin {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(0) });
+        stdin.read(buf)
+    }
+}
+
+/// Reads up to `delimiter` a byte at a time, so that nothing past it is
+/// consumed. Returns the text and whether the delimiter was found, or `None`
+/// at end of input.
+pub fn read_until(input: &mut dyn Read, delimiter: u8) -> Option<(String, bool)> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) if byte[0] == delimiter => return Some((String::from_utf8_lossy(&line).into_owned(), true)),
+            Ok(_) => line.push(byte[0]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+
+    if line.is_empty() {
+        None
+    } else {
+        Some((String::from_utf8_lossy(&line).into_owned(), false))
+    }
+}
+
+/// Reads one line from standard input, leaving the rest of it for child
+/// processes.
+pub fn read_stdin_line() -> Option<String> {
+    read_until(&mut RawStdin, b'\n').map(|(line, _)| line)
+}
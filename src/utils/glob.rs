@@ -0,0 +1,143 @@
+use std::fs;
+
+use super::pattern::Pattern;
+
+/// The `shopt` settings that change how patterns match file names.
+#[derive(Default, Clone, Copy)]
+pub struct GlobOptions {
+    /// `*` and `?` also match a leading `.`.
+    pub dotglob: bool,
+    pub nocaseglob: bool,
+}
+
+/// Expands a pathname pattern such as `src/*.rs` against the filesystem,
+/// one `/`-separated component at a time. Returns the matching paths in
+/// sorted order; names starting with `.` only match a pattern that starts
+/// with `.` unless `dotglob` is set.
+pub fn expand_glob(pattern: &str, options: GlobOptions) -> Vec<String> {
+    let mut paths = vec![if pattern.starts_with('/') { "/".to_string() } else { String::new() }];
+
+    for component in pattern.split('/').filter(|component| !component.is_empty()) {
+        let mut matched = Vec::new();
+        for path in &paths {
+            if !has_glob(component) {
+                matched.push(join(path, &unescape(component)));
+                continue;
+            }
+
+            let Ok(entries) = fs::read_dir(if path.is_empty() { "." } else { path }) else {
+                continue;
+            };
+            let matcher = Pattern::new(&if options.nocaseglob { component.to_lowercase() } else { component.to_string() });
+            for entry in entries.flatten() {
+                let Ok(name) = entry.file_name().into_string() else {
+                    continue;
+                };
+                if name.starts_with('.') && !component.starts_with('.') && !options.dotglob {
+                    continue;
+                }
+                let candidate = if options.nocaseglob { name.to_lowercase() } else { name.clone() };
+                if matcher.matches(&candidate.chars().collect::<Vec<char>>()) {
+                    matched.push(join(path, &name));
+                }
+            }
+        }
+        paths = matched;
+    }
+
+    // A trailing `/` only matches directories, and keeps the slash.
+    if pattern.ends_with('/') {
+        paths = paths.into_iter().filter(|path| fs::metadata(path).is_ok_and(|m| m.is_dir())).map(|path| path + "/").collect();
+    }
+    paths.retain(|path| fs::symlink_metadata(path).is_ok());
+    paths.sort();
+    paths
+}
+
+/// Whether `text` has an unescaped `*`, `?` or `[`.
+pub fn has_glob(text: &str) -> bool {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        unescaped.extend(if c == '\\' { chars.next() } else { Some(c) });
+    }
+    unescaped
+}
+
+fn join(directory: &str, name: &str) -> String {
+    match directory {
+        "" => name.to_string(),
+        "/" => format!("/{}", name),
+        _ => format!("{}/{}", directory, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory named after the test holding `a.rs`, `b.rs`, `.hidden.rs`,
+    /// `README` and `src/m.rs`, removed again when the closure returns.
+    fn with_tree(name: &str, test: impl FnOnce(&str)) {
+        let root = std::env::temp_dir().join(format!("rust-shell-glob-{}-{}", std::process::id(), name));
+        fs::create_dir_all(root.join("src")).unwrap();
+        for name in ["a.rs", "b.rs", ".hidden.rs", "README", "src/m.rs"] {
+            fs::write(root.join(name), "").unwrap();
+        }
+        let root_text = root.to_str().unwrap().to_string();
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| test(&root_text)));
+        let _ = fs::remove_dir_all(&root);
+        outcome.unwrap();
+    }
+
+    fn names(root: &str, pattern: &str, options: GlobOptions) -> Vec<String> {
+        expand_glob(&format!("{}/{}", root, pattern), options).into_iter()
+            .map(|path| path[root.len() + 1..].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn matches_components_in_sorted_order() {
+        with_tree("components", |root| {
+            assert_eq!(names(root, "*.rs", GlobOptions::default()), ["a.rs", "b.rs"]);
+            assert_eq!(names(root, "*/*.rs", GlobOptions::default()), ["src/m.rs"]);
+            assert_eq!(names(root, "*/", GlobOptions::default()), ["src/"]);
+            assert!(names(root, "*.txt", GlobOptions::default()).is_empty());
+            assert!(names(root, "\\*.rs", GlobOptions::default()).is_empty());
+        });
+    }
+
+    #[test]
+    fn dotglob_and_nocaseglob() {
+        with_tree("options", |root| {
+            assert_eq!(names(root, ".*.rs", GlobOptions::default()), [".hidden.rs"]);
+            let dotglob = GlobOptions { dotglob: true, ..Default::default() };
+            assert_eq!(names(root, "*.rs", dotglob), [".hidden.rs", "a.rs", "b.rs"]);
+            assert!(names(root, "read*", GlobOptions::default()).is_empty());
+            let nocaseglob = GlobOptions { nocaseglob: true, ..Default::default() };
+            assert_eq!(names(root, "read*", nocaseglob), ["README"]);
+        });
+    }
+
+    #[test]
+    fn has_glob_ignores_escaped_characters() {
+        assert!(has_glob("src/*.rs"));
+        assert!(has_glob("[ab]"));
+        assert!(!has_glob("a\\*b"));
+        assert!(!has_glob("plain"));
+    }
+}
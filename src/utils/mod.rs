@@ -1,6 +1,18 @@
 mod parser;
 mod files;
+mod cli;
+mod pattern;
+mod ast;
+mod spelling;
+mod glob;
+mod quote;
 
-pub use parser::ParsedCommand;
-pub use parser::parse_input;
-pub use files::open_file;
\ No newline at end of file
+pub use parser::{ParsedCommand, Pipeline, Word, WordPart, Parameter, ParameterForm, ParameterOp, ReplaceMode, Subscript, Assignment, AssignmentValue, FdRedirect, TimeFormat, AndOr};
+pub use parser::{KEYWORDS, parse_input, parse_prompt, is_incomplete, is_valid_name, logical_lines};
+pub use files::{open_file, open_input_file, read_stdin_line, read_until, RawStdin};
+pub use pattern::{Pattern, escape_pattern};
+pub use cli::{parse_shell_args, DumpMode, ScriptSource, ShellOption, StartupFiles};
+pub use ast::{dump_ast, format_function, format_pipeline, format_source};
+pub use spelling::closest_matches;
+pub use glob::{GlobOptions, expand_glob, has_glob};
+pub(crate) use quote::quote;
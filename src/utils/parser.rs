@@ -1,164 +1,1045 @@
-const SPECIAL_CHARS: &[&'static str] = &["\"", "\\"];
+use std::rc::Rc;
 
-#[derive(Debug)]
+const SPECIAL_CHARS: &[&str] = &["\"", "\\", "$"];
+const SPECIAL_PARAMETERS: &[char] = &['$', '!', '#', '@', '*', '-', '?'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WordPart {
+    Literal(String),
+    Quoted(String),
+    Parameter(Parameter),
+    /// `$(command)`, holding the command's source text.
+    Command { source: String, quoted: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subscript {
+    All,
+    Star,
+    Index(Word),
+}
+
+/// `${name}`, `${#name}` (length / element count), `${!name[@]}` (keys) or
+/// `${!prefix*}` (names of variables starting with `prefix`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterForm {
+    Value,
+    Length,
+    Keys,
+    Names,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaceMode {
+    First,
+    All,
+    Prefix,
+    Suffix,
+}
+
+/// The operator part of `${name<op>...}`. `check_null` is set for the colon
+/// forms (`:-`, `:=`, ...), which also treat an empty value as unset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterOp {
+    Default { word: Word, check_null: bool },
+    Assign { word: Word, check_null: bool },
+    Error { word: Word, check_null: bool },
+    Alternative { word: Word, check_null: bool },
+    RemovePrefix { pattern: Word, longest: bool },
+    RemoveSuffix { pattern: Word, longest: bool },
+    Replace { pattern: Word, replacement: Word, mode: ReplaceMode },
+    Substring { offset: Word, length: Option<Word> },
+    Case { upper: bool, all: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub subscript: Option<Subscript>,
+    pub form: ParameterForm,
+    pub op: Option<ParameterOp>,
+    pub quoted: bool,
+}
+
+impl Parameter {
+    fn simple(name: String, quoted: bool) -> Self {
+        Parameter { name, subscript: None, form: ParameterForm::Value, op: None, quoted }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Word {
+    pub parts: Vec<WordPart>,
+}
+
+impl Word {
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Returns the text of a word made only of unquoted characters, which is
+    /// how operators such as `|` and `>` are told apart from quoted arguments.
+    pub fn as_unquoted(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [WordPart::Literal(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    fn push_char(&mut self, c: char, quoted: bool) {
+        match (self.parts.last_mut(), quoted) {
+            (Some(WordPart::Literal(text)), false) | (Some(WordPart::Quoted(text)), true) => text.push(c),
+            _ if quoted => self.parts.push(WordPart::Quoted(c.to_string())),
+            _ => self.parts.push(WordPart::Literal(c.to_string())),
+        }
+    }
+
+    fn push_empty_quotes(&mut self) {
+        if self.parts.is_empty() {
+            self.parts.push(WordPart::Quoted(String::new()));
+        }
+    }
+
+    fn from_parts(first: &str, rest: &[WordPart]) -> Self {
+        let mut word = Word::default();
+        if !first.is_empty() {
+            word.parts.push(WordPart::Literal(first.to_string()));
+        }
+        word.parts.extend(rest.iter().cloned());
+        word
+    }
+
+    /// Splits a leading `NAME=` or `NAME[subscript]=` off an unquoted word,
+    /// leaving the value parts. Array literal elements use the same shape
+    /// with an empty name (`[key]=value`).
+    fn split_assignment(&self) -> Option<(String, Option<Word>, Word)> {
+        let Some(WordPart::Literal(first)) = self.parts.first() else {
+            return None;
+        };
+
+        let name_len = first.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(first.len());
+        let (name, rest) = first.split_at(name_len);
+
+        if let Some(value) = rest.strip_prefix('=') {
+            return is_valid_name(name).then(|| (name.to_string(), None, Word::from_parts(value, &self.parts[1..])));
+        }
+
+        let subscript_start = rest.strip_prefix('[')?;
+        if !name.is_empty() && !is_valid_name(name) {
+            return None;
+        }
+
+        let mut parts = vec![WordPart::Literal(subscript_start.to_string())];
+        parts.extend(self.parts[1..].iter().cloned());
+
+        let mut subscript = Word::default();
+        for (i, part) in parts.iter().enumerate() {
+            if let WordPart::Literal(text) = part {
+                if let Some(end) = text.find("]=") {
+                    if end > 0 {
+                        subscript.parts.push(WordPart::Literal(text[..end].to_string()));
+                    }
+                    let value = Word::from_parts(&text[end + 2..], &parts[i + 1..]);
+                    return Some((name.to_string(), Some(subscript), value));
+                }
+                if text.is_empty() {
+                    continue;
+                }
+            }
+            subscript.parts.push(part.clone());
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrayElement {
+    pub key: Option<Word>,
+    pub value: Word,
+}
+
+#[derive(Debug, Clone)]
+pub enum AssignmentValue {
+    Scalar(Word),
+    Array(Vec<ArrayElement>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub name: String,
+    pub subscript: Option<Word>,
+    pub value: AssignmentValue,
+}
+
+#[derive(Debug, Default)]
 pub struct ParsedCommand {
-    pub command: String,
-    pub args: Vec<String>,
+    pub assignments: Vec<Assignment>,
+    pub words: Vec<Word>,
 
-    pub stdout_redirect: Option<String>,
-    pub stderr_redirect: Option<String>,
+    /// Redirections in the order they were written, which is the order
+    /// they apply in: `2>&1 >file` leaves stderr where stdout was before.
+    pub redirects: Vec<FdRedirect>,
 
-    pub stdout_redirect_append: bool,
-    pub stderr_redirect_append: bool,
+    /// `name() { ... }`, which defines a function instead of running anything.
+    pub function: Option<FunctionDefinition>,
 }
 
-pub fn parse_input(input: &str) -> Vec<ParsedCommand> {
-    let tokens = tokenize_input(input);
-    let mut commands: Vec<ParsedCommand> = Vec::new();
+#[derive(Debug, Clone, PartialEq)]
+pub enum FdRedirect {
+    /// `>file`, `2>>file`, `<file` or `3>file`.
+    Open { fd: i32, operator: &'static str, path: Word },
+    /// `2>&1` or `>&3`: `fd` becomes a copy of `target`.
+    Duplicate { fd: i32, target: i32 },
+    /// `3>&-`.
+    Close { fd: i32 },
+}
+
+#[derive(Debug)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub body: Rc<Vec<Pipeline>>,
+}
 
-    for token in tokens.split(|t| t == "|") {
-        if token.is_empty() {
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug)]
+pub struct Pipeline {
+    pub commands: Vec<ParsedCommand>,
+    /// Terminated by `&` rather than `;` or the end of the line.
+    pub background: bool,
+    /// Prefixed with the `time` keyword.
+    pub time: Option<TimeFormat>,
+    /// Prefixed with `!`, which inverts the exit status.
+    pub negated: bool,
+    /// Follows `&&` or `||`, so it only runs if the previous pipeline
+    /// succeeded or failed.
+    pub and_or: Option<AndOr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AndOr {
+    And,
+    Or,
+}
+
+/// How `time` reports: as `$TIMEFORMAT` says, or in the POSIX format for
+/// `time -p`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeFormat {
+    Variable,
+    Posix,
+}
+
+/// Parses a line (or a multi-line function definition) into pipelines. A
+/// syntax error anywhere rejects the whole input.
+pub fn parse_input(input: &str) -> Result<Vec<Pipeline>, String> {
+    parse_tokens(&tokenize_input(input)?)
+}
+
+fn parse_tokens(mut tokens: &[Word]) -> Result<Vec<Pipeline>, String> {
+    let mut pipelines: Vec<Pipeline> = Vec::new();
+    let mut next_and_or = None;
+
+    while !tokens.is_empty() {
+        if let Some((name, header)) = function_header(tokens) {
+            let Some((header, end)) = header.and_then(|header| Some((header, body_end(&tokens[header..])?))) else {
+                return Err("Syntax error: expected `}' to close function body".to_string());
+            };
+            let body = Rc::new(parse_tokens(&tokens[header..header + end])?);
+            let command = ParsedCommand { function: Some(FunctionDefinition { name, body }), ..Default::default() };
+            pipelines.push(Pipeline { commands: vec![command], background: false, time: None, negated: false, and_or: next_and_or.take() });
+
+            tokens = &tokens[header + end + 1..];
+            next_and_or = match tokens.first().and_then(Word::as_unquoted) {
+                Some("&&") => Some(AndOr::And),
+                Some("||") => Some(AndOr::Or),
+                _ => None,
+            };
+            if tokens.first().is_some_and(|t| matches!(t.as_unquoted(), Some(";" | "&&" | "||"))) {
+                tokens = &tokens[1..];
+            }
             continue;
         }
 
-        if let Some(parsed_command) = parse_command_line(token.to_vec()) {
-            commands.push(parsed_command);
+        let end = tokens.iter().position(|t| matches!(t.as_unquoted(), Some(";" | "&" | "&&" | "||"))).map_or(tokens.len(), |i| i + 1);
+        let (segment, rest) = tokens.split_at(end);
+        tokens = rest;
+
+        let (separator, segment) = match segment.split_last() {
+            Some((last, rest)) if matches!(last.as_unquoted(), Some(";" | "&" | "&&" | "||")) => (last.as_unquoted(), rest),
+            _ => (None, segment),
+        };
+
+        // `time` and `!` may come in either order.
+        let (time, segment) = split_time(segment);
+        let (negated, segment) = match segment.split_first() {
+            Some((first, rest)) if first.as_unquoted() == Some("!") => (true, rest),
+            _ => (false, segment),
+        };
+        let (time, segment) = match time {
+            Some(_) => (time, segment),
+            None => split_time(segment),
+        };
+        let commands = parse_pipeline(segment)?;
+        let and_or = next_and_or.take();
+
+        if commands.is_empty() && time.is_none() {
+            // A stray `;` is harmless, but the operators need a command on
+            // both sides.
+            if and_or.is_some() || matches!(separator, Some("&" | "&&" | "||")) {
+                return Err(format!("Syntax error: unexpected `{}'", separator.unwrap_or("newline")));
+            }
+            continue;
         }
+
+        next_and_or = match separator {
+            Some("&&") => Some(AndOr::And),
+            Some("||") => Some(AndOr::Or),
+            _ => None,
+        };
+        pipelines.push(Pipeline { commands, background: separator == Some("&"), time, negated, and_or });
     }
 
-    commands
+    if next_and_or.is_some() {
+        return Err("Syntax error: unexpected end of input after `&&' or `||'".to_string());
+    }
+
+    Ok(pipelines)
+}
+
+/// Words the parser treats as syntax when they start a command.
+pub const KEYWORDS: &[&str] = &["function", "time", "{", "}"];
+
+/// Takes the `time` keyword (and `-p`) off the front of a pipeline.
+fn split_time(tokens: &[Word]) -> (Option<TimeFormat>, &[Word]) {
+    match tokens {
+        [first, second, rest @ ..] if first.as_unquoted() == Some("time") && second.as_unquoted() == Some("-p") => {
+            (Some(TimeFormat::Posix), rest)
+        }
+        [first, rest @ ..] if first.as_unquoted() == Some("time") => (Some(TimeFormat::Variable), rest),
+        _ => (None, tokens),
+    }
 }
 
+/// Recognises `name() {` or `function name {` at the start of `tokens`.
+/// Returns the name and the index just past the `{`, which is `None` while
+/// the body hasn't started yet (`name()` at the end of a line).
+fn function_header(tokens: &[Word]) -> Option<(String, Option<usize>)> {
+    let words: Vec<Option<&str>> = tokens.iter().map(Word::as_unquoted).collect();
 
-pub fn parse_command_line(tokens: Vec<String>) -> Option<ParsedCommand> {
-    let command = tokens[0].clone();
-    let mut args = Vec::new();
-    let mut stdout_redirect = None;
-    let mut stderr_redirect = None;
+    let (name, mut index) = match words.as_slice() {
+        [Some("function"), Some(name), Some("("), Some(")"), ..] => (name, 4),
+        [Some("function"), Some(name), ..] => (name, 2),
+        [Some(name), Some("("), Some(")"), ..] => (name, 3),
+        _ => return None,
+    };
+    if !is_function_name(name) {
+        return None;
+    }
 
-    let mut stdout_redirect_append = false;
-    let mut stderr_redirect_append = false;
+    // The body may start on the next line.
+    while words.get(index) == Some(&Some(";")) {
+        index += 1;
+    }
+    match words.get(index) {
+        Some(Some("{")) => Some((name.to_string(), Some(index + 1))),
+        None => Some((name.to_string(), None)),
+        Some(_) => None,
+    }
+}
 
-    let mut iter = tokens.iter().skip(1).peekable();
+/// Function names are looser than variable names (`my-func` is fine), but
+/// can't contain anything that reads as syntax or a path.
+fn is_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && !KEYWORDS.contains(&name)
+        && !name.contains(['/', '=', '(', ')', ';', '&', '|', '<', '>'])
+}
 
-    while let Some(token) = iter.next() {
-        match token.as_str() {
-            ">>" | "1>>" => {
-                if let Some(path) = iter.next() {
-                    stdout_redirect = Some(path.clone());
-                    stdout_redirect_append = true;
-                } else {
-                    eprintln!("Syntax error: expected file path after redirect");
-                }
-            }
-            "2>>" => {
-                if let Some(path) = iter.next() {
-                    stderr_redirect = Some(path.clone());
-                    stderr_redirect_append = true;
-                } else {
-                    eprintln!("Syntax error: expected file path after redirect");
+/// Finds the `}` that closes a function body starting at `tokens`. Braces
+/// only count in command position, so `echo }` doesn't end the body.
+fn body_end(tokens: &[Word]) -> Option<usize> {
+    let mut depth = 1;
+    let mut command_start = true;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let text = token.as_unquoted();
+        match text {
+            Some("{") if command_start => depth += 1,
+            Some("}") if command_start => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
                 }
             }
-            ">" | "1>" => {
-                if let Some(path) = iter.next() {
-                    stdout_redirect = Some(path.clone());
-                } else {
-                    eprintln!("Syntax error: expected file path after redirect");
+            _ => {}
+        }
+        command_start = matches!(text, Some(";" | "&" | "|" | "&&" | "||" | "{" | "}" | ")"));
+    }
+    None
+}
+
+/// Whether `input` stops inside quotes, inside a function definition or
+/// after `&&` / `||`, so the caller should read more lines before running it.
+pub fn is_incomplete(input: &str) -> bool {
+    let Ok(tokens) = tokenize_input(input) else {
+        return true;
+    };
+    let mut index = 0;
+    let mut command_start = true;
+
+    while index < tokens.len() {
+        if command_start && let Some((_, header)) = function_header(&tokens[index..]) {
+            let Some(end) = header.and_then(|header| Some(header + body_end(&tokens[index + header..])?)) else {
+                return true;
+            };
+            index += end + 1;
+            continue;
+        }
+        command_start = matches!(tokens[index].as_unquoted(), Some(";" | "&" | "|" | "&&" | "||"));
+        index += 1;
+    }
+    // A line ending in `&&` or `||` continues on the next one.
+    tokens.last().is_some_and(|token| matches!(token.as_unquoted(), Some("&&" | "||")))
+}
+
+/// Splits a script into the pieces to run one at a time: single lines,
+/// except that a function definition spanning several lines, or a line
+/// ending in `&&` or `||` or inside quotes, stays whole with what follows.
+/// Each piece comes with the number of its first line.
+pub fn logical_lines(source: &str) -> Vec<(usize, String)> {
+    let mut pieces = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let (number, text) = match pending.take() {
+            Some((number, text)) => (number, format!("{}\n{}", text, line)),
+            None => (index + 1, line.to_string()),
+        };
+        if is_incomplete(&text) {
+            pending = Some((number, text));
+        } else {
+            pieces.push((number, text));
+        }
+    }
+
+    pieces.extend(pending);
+    pieces
+}
+
+fn parse_pipeline(tokens: &[Word]) -> Result<Vec<ParsedCommand>, String> {
+    let mut commands: Vec<ParsedCommand> = Vec::new();
+    if tokens.is_empty() {
+        return Ok(commands);
+    }
+
+    let stages: Vec<&[Word]> = tokens.split(|t| t.as_unquoted() == Some("|")).collect();
+    for (index, stage) in stages.iter().enumerate() {
+        if stage.is_empty() {
+            return Err(if index + 1 == stages.len() {
+                "Syntax error: expected a command after `|'".to_string()
+            } else {
+                "Syntax error: unexpected `|'".to_string()
+            });
+        }
+
+        if let Some(parsed_command) = parse_command_line(stage.to_vec())? {
+            commands.push(parsed_command);
+        }
+    }
+
+    Ok(commands)
+}
+
+
+const REDIRECT_OPERATORS: &[&str] = &[">>", ">&", "<&", ">", "<"];
+
+/// Splits a redirection off the front of a token, so `2>/dev/null` works as
+/// well as `2> /dev/null`. Returns the descriptor number written before the
+/// operator, the operator and the target attached to it, if any.
+fn split_redirect(token: &Word) -> Option<(Option<i32>, &'static str, Option<Word>)> {
+    let Some(WordPart::Literal(text)) = token.parts.first() else {
+        return None;
+    };
+    let digits = text.find(|c: char| !c.is_ascii_digit())?;
+    let operator = REDIRECT_OPERATORS.iter().find(|operator| text[digits..].starts_with(**operator))?;
+    let fd = if digits == 0 { None } else { Some(text[..digits].parse().ok()?) };
+
+    let mut target = token.clone();
+    match &text[digits + operator.len()..] {
+        "" => { target.parts.remove(0); }
+        rest => target.parts[0] = WordPart::Literal(rest.to_string()),
+    }
+    Some((fd, operator, (!target.is_empty()).then_some(target)))
+}
+
+pub fn parse_command_line(tokens: Vec<Word>) -> Result<Option<ParsedCommand>, String> {
+    let mut assignments = Vec::new();
+    let mut words = Vec::new();
+    let mut redirects = Vec::new();
+
+    let mut iter = tokens.into_iter().peekable();
+
+    while let Some(token) = iter.next() {
+        let Some((fd, operator, target)) = split_redirect(&token) else {
+            match token.split_assignment() {
+                Some((name, subscript, value)) if words.is_empty() && !name.is_empty() => {
+                    let starts_array = subscript.is_none() && value.is_empty()
+                        && iter.peek().is_some_and(|next| next.as_unquoted() == Some("("));
+
+                    let value = if starts_array {
+                        iter.next();
+                        AssignmentValue::Array(parse_array_elements(&mut iter)?)
+                    } else {
+                        AssignmentValue::Scalar(value)
+                    };
+                    assignments.push(Assignment { name, subscript, value });
                 }
+                _ => words.push(token),
             }
-            "2>" => {
-                if let Some(path) = iter.next() {
-                    stderr_redirect = Some(path.clone());
-                } else {
-                    eprintln!("Syntax error: expected file path after redirect");
+            continue;
+        };
+
+        let Some(target) = target.or_else(|| iter.next()) else {
+            return Err(format!("Syntax error: expected file path after `{}'", operator));
+        };
+
+        let fd = fd.unwrap_or(if operator.starts_with('<') { 0 } else { 1 });
+        if operator.ends_with('&') {
+            let text = target.as_unquoted();
+            match (fd, text, text.and_then(|text| text.parse::<i32>().ok())) {
+                (_, _, Some(target)) => redirects.push(FdRedirect::Duplicate { fd, target }),
+                (_, Some("-"), _) => redirects.push(FdRedirect::Close { fd }),
+                // `>&file` sends both stdout and stderr to the file.
+                (1, _, _) if operator == ">&" => {
+                    redirects.push(FdRedirect::Open { fd: 1, operator: ">", path: target });
+                    redirects.push(FdRedirect::Duplicate { fd: 2, target: 1 });
                 }
+                _ => return Err(format!("Syntax error: {}{}: ambiguous redirect", fd, operator)),
             }
-            _ => {
-                args.push(token.clone());
+            continue;
+        }
+
+        redirects.push(FdRedirect::Open { fd, operator, path: target });
+    }
+
+    if words.is_empty() && assignments.is_empty() && redirects.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(ParsedCommand { assignments, words, redirects, function: None }))
+}
+
+
+/// Collects the elements of an `name=(...)` array literal up to the closing
+/// parenthesis, keeping `[key]=value` elements keyed.
+fn parse_array_elements(tokens: &mut impl Iterator<Item = Word>) -> Result<Vec<ArrayElement>, String> {
+    let mut elements = Vec::new();
+
+    for token in tokens.by_ref() {
+        if token.as_unquoted() == Some(")") {
+            return Ok(elements);
+        }
+
+        match token.split_assignment() {
+            Some((name, Some(key), value)) if name.is_empty() => {
+                elements.push(ArrayElement { key: Some(key), value });
             }
+            _ => elements.push(ArrayElement { key: None, value: token }),
         }
     }
 
-    Some(ParsedCommand {
-        command,
-        args,
-        stdout_redirect,
-        stderr_redirect,
-        stdout_redirect_append,
-        stderr_redirect_append,
-    })
+    Err("Syntax error: expected `)' to close array assignment".to_string())
 }
 
 
-pub fn tokenize_input(input: &str) -> Vec<String> {
+/// Splits input into words and operators. Quotes left open are an error.
+pub fn tokenize_input(input: &str) -> Result<Vec<Word>, String> {
     let mut chars = input.chars().peekable();
 
-    let mut args: Vec<String> = Vec::new();
-    let mut current_arg: String = String::new();
+    let mut args: Vec<Word> = Vec::new();
+    let mut current_arg = Word::default();
 
     let mut in_quotes:bool = false;
     let mut in_double_quotes: bool = false;
     let mut escape_next: bool = false;
 
     while let Some(c) = chars.next() {
+        if escape_next {
+            current_arg.push_char(c, true);
+            escape_next = false;
+            continue;
+        }
+
         match c {
             '\\' => {
-                if (!in_double_quotes && !in_quotes && !escape_next) || 
-                    (chars.peek().map_or(false, |next_c| SPECIAL_CHARS.contains(&next_c.to_string().as_str())) 
-                    && !escape_next && in_double_quotes){
+                let escapes_next = !in_quotes && (!in_double_quotes
+                    || chars.peek().is_some_and(|next_c| SPECIAL_CHARS.contains(&next_c.to_string().as_str())));
+                if escapes_next {
                     escape_next = true;
                 } else {
-                    current_arg.push(c);
-                    escape_next = false;
+                    current_arg.push_char(c, true);
                 }
             }
-            '\'' => {
-                if !in_double_quotes && !escape_next {
-                    in_quotes = !in_quotes;
-                } else {
-                    current_arg.push(c);
-                    escape_next = false;
+            '\'' if !in_double_quotes => {
+                in_quotes = !in_quotes;
+                if !in_quotes {
+                    current_arg.push_empty_quotes();
                 }
             },
-            '"' => {
-                if !in_quotes && !escape_next {
-                    in_double_quotes = !in_double_quotes;
-                } else {
-                    current_arg.push(c);
-                    escape_next = false;
+            '"' if !in_quotes => {
+                in_double_quotes = !in_double_quotes;
+                if !in_double_quotes {
+                    current_arg.push_empty_quotes();
                 }
             },
-            '|' => {
-                if in_quotes || in_double_quotes || escape_next {
-                    current_arg.push(c);
-                    escape_next = false;
-                } else {
-                    if !current_arg.is_empty() {
-                        args.push(current_arg);
-                        current_arg = String::new();
-                    }
-                    args.push("|".to_string());
+            '$' if !in_quotes && chars.peek() == Some(&'(') => {
+                match read_command_substitution(&mut chars) {
+                    Some(source) => current_arg.parts.push(WordPart::Command { source, quoted: in_double_quotes }),
+                    None => current_arg.push_char(c, in_double_quotes),
+                }
+            },
+            '$' if !in_quotes => {
+                match read_parameter(&mut chars, in_double_quotes) {
+                    Some(parameter) => current_arg.parts.push(WordPart::Parameter(parameter)),
+                    None => current_arg.push_char(c, in_double_quotes),
+                }
+            },
+            // A comment runs to the end of its line.
+            '#' if !in_quotes && !in_double_quotes && current_arg.is_empty() => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            },
+            // Newlines only show up inside multi-line function definitions,
+            // where they separate commands, and after `&&` or `||`, where
+            // they don't.
+            '\n' if !in_quotes && !in_double_quotes => {
+                if !current_arg.is_empty() {
+                    args.push(std::mem::take(&mut current_arg));
+                }
+                if !args.last().is_some_and(|token| matches!(token.as_unquoted(), Some("&&" | "||"))) {
+                    args.push(Word { parts: vec![WordPart::Literal(";".to_string())] });
                 }
             },
-            c if c.is_whitespace()=> {
-                if in_quotes || in_double_quotes || escape_next {
-                    current_arg.push(c);
-                    escape_next = false;
-                } else if !current_arg.is_empty() {
-                    args.push(current_arg);
-                    current_arg = String::new();
+            // A redirection ends the word before it, unless that word is the
+            // descriptor number (`2>`) or the operator so far (`>>`).
+            '>' | '<' if !in_quotes && !in_double_quotes => {
+                let continues_operator = current_arg.as_unquoted()
+                    .is_some_and(|text| text.chars().all(|c| c.is_ascii_digit() || c == '>'));
+                if !current_arg.is_empty() && !continues_operator {
+                    args.push(std::mem::take(&mut current_arg));
+                }
+                current_arg.push_char(c, false);
+            },
+            // `>&` and `<&` duplicate a descriptor (`2>&1`) rather than ending the command.
+            '&' if !in_quotes && !in_double_quotes && current_arg.as_unquoted().is_some_and(|text| text.ends_with(['>', '<'])) => {
+                current_arg.push_char(c, false);
+            },
+            '|' | ';' | '&' | '(' | ')' if !in_quotes && !in_double_quotes => {
+                if !current_arg.is_empty() {
+                    args.push(std::mem::take(&mut current_arg));
+                }
+                let mut operator = c.to_string();
+                // `&&` and `||` are operators of their own.
+                if let Some(second) = chars.next_if(|&next| matches!(c, '&' | '|') && next == c) {
+                    operator.push(second);
+                }
+                args.push(Word { parts: vec![WordPart::Literal(operator)] });
+            },
+            c if c.is_whitespace() && !in_quotes && !in_double_quotes => {
+                if !current_arg.is_empty() {
+                    args.push(std::mem::take(&mut current_arg));
                 }
             },
             _ => {
-                current_arg.push(c);
-                escape_next = false;
+                current_arg.push_char(c, in_quotes || in_double_quotes);
             }
         }
     }
 
+    if in_quotes || in_double_quotes {
+        let quote = if in_quotes { '\'' } else { '"' };
+        return Err(format!("Syntax error: unexpected end of input while looking for matching `{}'", quote));
+    }
     if !current_arg.is_empty() {
         args.push(current_arg);
     }
 
-    args
-}
\ No newline at end of file
+    Ok(args)
+}
+
+/// Reads the body of `$(...)` up to the matching parenthesis, skipping over
+/// quoted text. Leaves `chars` untouched if the parenthesis is never closed.
+fn read_command_substitution(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut lookahead = chars.clone();
+    lookahead.next();
+
+    let mut source = String::new();
+    let mut depth = 1;
+    let mut quote: Option<char> = None;
+
+    loop {
+        let c = lookahead.next()?;
+        match (c, quote) {
+            ('\\', Some('\'')) => {}
+            ('\\', _) => {
+                source.push(c);
+                source.push(lookahead.next()?);
+                continue;
+            }
+            (q, None) if q == '\'' || q == '"' => quote = Some(q),
+            (q, Some(open)) if q == open => quote = None,
+            ('(', None) => depth += 1,
+            (')', None) => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+        source.push(c);
+    }
+
+    *chars = lookahead;
+    Some(source)
+}
+
+/// Reads the parameter following a `$`: `${...}`, a single digit, one of the
+/// special parameters or a regular variable name.
+fn read_parameter(chars: &mut std::iter::Peekable<std::str::Chars>, quoted: bool) -> Option<Parameter> {
+    match chars.peek().copied() {
+        Some('{') => {
+            let mut lookahead = chars.clone();
+            lookahead.next();
+
+            let mut body = String::new();
+            let mut depth = 1;
+            loop {
+                let c = lookahead.next()?;
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    '\\' => {
+                        body.push(c);
+                        body.push(lookahead.next()?);
+                        continue;
+                    }
+                    _ => {}
+                }
+                body.push(c);
+            }
+
+            let parameter = parse_braced_parameter(&body, quoted)?;
+            *chars = lookahead;
+            Some(parameter)
+        }
+        Some(c) if c.is_ascii_digit() || SPECIAL_PARAMETERS.contains(&c) => {
+            chars.next();
+            Some(Parameter::simple(c.to_string(), quoted))
+        }
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            Some(Parameter::simple(name, quoted))
+        }
+        _ => None,
+    }
+}
+
+/// Parses the inside of `${...}`: an optional `#` or `!` prefix, the name,
+/// an optional `[subscript]` and an optional operator.
+fn parse_braced_parameter(body: &str, quoted: bool) -> Option<Parameter> {
+    if let Some(prefix) = body.strip_prefix('!').and_then(|rest| rest.strip_suffix(['*', '@']))
+        && is_valid_name(prefix) {
+        return Some(Parameter {
+            name: prefix.to_string(),
+            subscript: body.ends_with('*').then_some(Subscript::Star),
+            form: ParameterForm::Names,
+            op: None,
+            quoted,
+        });
+    }
+
+    let (form, rest) = match body.split_at_checked(1) {
+        Some(("#", rest)) if !rest.is_empty() => (ParameterForm::Length, rest),
+        Some(("!", rest)) if !rest.is_empty() => (ParameterForm::Keys, rest),
+        _ => (ParameterForm::Value, body),
+    };
+
+    let name_len = match rest.chars().next()? {
+        c if SPECIAL_PARAMETERS.contains(&c) => 1,
+        c if c.is_ascii_digit() => rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len()),
+        _ => rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len()),
+    };
+    let (name, rest) = rest.split_at(name_len);
+    if name.is_empty() || !(is_valid_name(name) || name.len() == 1 || name.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+
+    let (subscript, rest) = match rest.strip_prefix('[') {
+        Some(inner) => {
+            let end = inner.find(']')?;
+            let subscript = match &inner[..end] {
+                "@" => Subscript::All,
+                "*" => Subscript::Star,
+                index => Subscript::Index(parse_embedded_word(index, true)),
+            };
+            (Some(subscript), &inner[end + 1..])
+        }
+        None => (None, rest),
+    };
+
+    if form == ParameterForm::Keys && !matches!(subscript, Some(Subscript::All | Subscript::Star)) {
+        return None;
+    }
+
+    let op = parse_parameter_op(rest, quoted)?;
+    if form != ParameterForm::Value && op.is_some() {
+        return None;
+    }
+
+    Some(Parameter { name: name.to_string(), subscript, form, op, quoted })
+}
+
+/// Parses what follows the name in `${name...}`. Returns `None` for text that
+/// is not a known operator, `Some(None)` when there is no operator at all.
+fn parse_parameter_op(rest: &str, quoted: bool) -> Option<Option<ParameterOp>> {
+    let word = |text: &str| parse_embedded_word(text, quoted);
+    // A pattern stays a pattern inside `"${...}"`; only quotes within the
+    // braces make its characters literal.
+    let pattern_word = |text: &str| parse_embedded_word(text, false);
+
+    let (check_null, test) = match rest.strip_prefix(':') {
+        Some(after) if after.starts_with(['-', '=', '?', '+']) => (true, after),
+        Some(_) => (false, ""),
+        None => (false, rest),
+    };
+
+    let op = match test.chars().next() {
+        Some('-') => ParameterOp::Default { word: word(&test[1..]), check_null },
+        Some('=') => ParameterOp::Assign { word: word(&test[1..]), check_null },
+        Some('?') => ParameterOp::Error { word: word(&test[1..]), check_null },
+        Some('+') => ParameterOp::Alternative { word: word(&test[1..]), check_null },
+        _ => match rest {
+            "" => return Some(None),
+            "^^" | "^" | ",," | "," => ParameterOp::Case { upper: rest.starts_with('^'), all: rest.len() == 2 },
+            _ if rest.starts_with("##") => ParameterOp::RemovePrefix { pattern: pattern_word(&rest[2..]), longest: true },
+            _ if rest.starts_with('#') => ParameterOp::RemovePrefix { pattern: pattern_word(&rest[1..]), longest: false },
+            _ if rest.starts_with("%%") => ParameterOp::RemoveSuffix { pattern: pattern_word(&rest[2..]), longest: true },
+            _ if rest.starts_with('%') => ParameterOp::RemoveSuffix { pattern: pattern_word(&rest[1..]), longest: false },
+            _ if rest.starts_with('/') => {
+                let (mode, spec) = match &rest[1..] {
+                    spec if spec.starts_with('/') => (ReplaceMode::All, &spec[1..]),
+                    spec if spec.starts_with('#') => (ReplaceMode::Prefix, &spec[1..]),
+                    spec if spec.starts_with('%') => (ReplaceMode::Suffix, &spec[1..]),
+                    spec => (ReplaceMode::First, spec),
+                };
+                let (pattern, replacement) = split_top_level(spec, '/').unwrap_or((spec, ""));
+                ParameterOp::Replace { pattern: pattern_word(pattern), replacement: word(replacement), mode }
+            }
+            _ if rest.starts_with(':') => {
+                let spec = &rest[1..];
+                match split_top_level(spec, ':') {
+                    Some((offset, length)) => ParameterOp::Substring { offset: word(offset), length: Some(word(length)) },
+                    None => ParameterOp::Substring { offset: word(spec), length: None },
+                }
+            }
+            _ => return None,
+        },
+    };
+
+    Some(Some(op))
+}
+
+/// Splits at the first `separator` that is not escaped, quoted or inside a
+/// nested `${...}`.
+fn split_top_level(text: &str, separator: char) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut in_quotes = false;
+    let mut in_double_quotes = false;
+    let mut chars = text.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if !in_quotes => {
+                chars.next();
+            }
+            '\'' if !in_double_quotes => in_quotes = !in_quotes,
+            '"' if !in_quotes => in_double_quotes = !in_double_quotes,
+            '{' if !in_quotes => depth += 1,
+            '}' if !in_quotes => depth -= 1,
+            c if c == separator && depth == 0 && !in_quotes && !in_double_quotes => {
+                return Some((&text[..i], &text[i + c.len_utf8()..]));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Parses a prompt string such as `PS4`, which is expanded as if it were in
+/// double quotes.
+pub fn parse_prompt(text: &str) -> Word {
+    parse_embedded_word(text, true)
+}
+
+/// Parses a word embedded in `${...}` (an operator's operand or an array
+/// subscript). Quotes and expansions work as usual, but whitespace does not
+/// separate words. Inside double quotes single quotes are literal.
+fn parse_embedded_word(text: &str, quoted: bool) -> Word {
+    let mut word = Word::default();
+    let mut chars = text.chars().peekable();
+
+    let mut in_quotes = false;
+    let mut in_double_quotes = quoted;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => word.push_char(c, true),
+            '\\' if in_double_quotes => {
+                match chars.next_if(|next_c| SPECIAL_CHARS.contains(&next_c.to_string().as_str()) || *next_c == '}') {
+                    Some(escaped) => word.push_char(escaped, true),
+                    None => word.push_char(c, true),
+                }
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    word.push_char(escaped, true);
+                }
+            }
+            '\'' if !in_double_quotes => {
+                in_quotes = !in_quotes;
+                if !in_quotes {
+                    word.push_empty_quotes();
+                }
+            }
+            '"' if quoted => word.push_empty_quotes(),
+            '"' if !in_quotes => {
+                in_double_quotes = !in_double_quotes;
+                if !in_double_quotes {
+                    word.push_empty_quotes();
+                }
+            }
+            '$' if !in_quotes && chars.peek() == Some(&'(') => match read_command_substitution(&mut chars) {
+                Some(source) => word.parts.push(WordPart::Command { source, quoted: in_double_quotes }),
+                None => word.push_char(c, in_double_quotes),
+            },
+            '$' if !in_quotes => match read_parameter(&mut chars, in_double_quotes) {
+                Some(parameter) => word.parts.push(WordPart::Parameter(parameter)),
+                None => word.push_char(c, in_double_quotes),
+            },
+            _ => word.push_char(c, in_quotes || in_double_quotes),
+        }
+    }
+
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn and_or_applies_to_function_definitions() {
+        let pipelines = parse_input("true || f() { echo hi; }; echo after").unwrap();
+        assert_eq!(pipelines.len(), 3);
+        assert!(pipelines[1].commands[0].function.is_some());
+        assert_eq!(pipelines[1].and_or, Some(AndOr::Or));
+        assert_eq!(pipelines[2].and_or, None);
+
+        let pipelines = parse_input("false && f() { :; }; ls &").unwrap();
+        assert_eq!(pipelines[1].and_or, Some(AndOr::And));
+        assert_eq!(pipelines[2].and_or, None);
+        assert!(pipelines[2].background);
+    }
+
+    #[test]
+    fn redirections_keep_their_order() {
+        let pipelines = parse_input("ls 2>&1 >out").unwrap();
+        let redirects = &pipelines[0].commands[0].redirects;
+        assert_eq!(redirects[0], FdRedirect::Duplicate { fd: 2, target: 1 });
+        assert!(matches!(&redirects[1], FdRedirect::Open { fd: 1, operator: ">", .. }));
+    }
+
+    #[test]
+    fn and_or_may_follow_a_function_definition() {
+        let pipelines = parse_input("f() { :; } && echo defined").unwrap();
+        assert_eq!(pipelines.len(), 2);
+        assert_eq!(pipelines[1].and_or, Some(AndOr::And));
+    }
+
+    #[test]
+    fn time_and_negation_prefix_a_pipeline() {
+        let pipelines = parse_input("time -p ! false | true; time ls; ! true; ! time -p true").unwrap();
+        assert_eq!(pipelines[0].time, Some(TimeFormat::Posix));
+        assert!(pipelines[0].negated);
+        assert_eq!(pipelines[0].commands.len(), 2);
+        assert_eq!(pipelines[1].time, Some(TimeFormat::Variable));
+        assert!(!pipelines[1].negated);
+        assert_eq!(pipelines[2].time, None);
+        assert!(pipelines[2].negated);
+        assert_eq!(pipelines[3].time, Some(TimeFormat::Posix));
+        assert!(pipelines[3].negated);
+        assert_eq!(pipelines[3].commands[0].words.len(), 1);
+    }
+
+    #[test]
+    fn assignments_and_array_literals() {
+        let pipelines = parse_input("a=1 b[2]=x c=(one [k]=two) cmd").unwrap();
+        let command = &pipelines[0].commands[0];
+        assert_eq!(command.words.len(), 1);
+        let names: Vec<&str> = command.assignments.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert!(command.assignments[1].subscript.is_some());
+        let AssignmentValue::Array(elements) = &command.assignments[2].value else {
+            panic!("expected an array literal");
+        };
+        assert!(elements[0].key.is_none());
+        assert!(elements[1].key.is_some());
+    }
+
+    #[test]
+    fn braced_parameter_forms() {
+        let parameter = |input: &str| match &parse_input(input).unwrap()[0].commands[0].words[1].parts[0] {
+            WordPart::Parameter(parameter) => parameter.clone(),
+            other => panic!("expected a parameter, got {:?}", other),
+        };
+        assert_eq!(parameter("echo ${#a[@]}").form, ParameterForm::Length);
+        assert_eq!(parameter("echo ${!a[@]}").form, ParameterForm::Keys);
+        assert_eq!(parameter("echo ${!pre*}").form, ParameterForm::Names);
+        assert_eq!(parameter("echo ${a[*]}").subscript, Some(Subscript::Star));
+        assert!(matches!(parameter("echo ${x:-d}").op, Some(ParameterOp::Default { check_null: true, .. })));
+        assert!(matches!(parameter("echo ${x##*/}").op, Some(ParameterOp::RemovePrefix { longest: true, .. })));
+        assert!(matches!(parameter("echo ${x//a/b}").op, Some(ParameterOp::Replace { mode: ReplaceMode::All, .. })));
+        assert!(matches!(parameter("echo ${x^^}").op, Some(ParameterOp::Case { upper: true, all: true })));
+    }
+
+    #[test]
+    fn syntax_errors_reject_the_input() {
+        assert_eq!(parse_input("echo a &&").unwrap_err(), "Syntax error: unexpected end of input after `&&' or `||'");
+        assert_eq!(parse_input("echo a >").unwrap_err(), "Syntax error: expected file path after `>'");
+        assert_eq!(parse_input("| b").unwrap_err(), "Syntax error: unexpected `|'");
+        assert_eq!(parse_input("a |").unwrap_err(), "Syntax error: expected a command after `|'");
+        assert_eq!(parse_input("x=(a b").unwrap_err(), "Syntax error: expected `)' to close array assignment");
+        assert!(parse_input("echo 'open").is_err());
+        assert!(is_incomplete("echo 'open"));
+        assert!(parse_input("; echo a").is_ok());
+    }
+}
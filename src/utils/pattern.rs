@@ -0,0 +1,190 @@
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    Class { ranges: Vec<(char, char)>, negated: bool },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::Any => true,
+            Token::Star => false,
+            Token::Class { ranges, negated } => {
+                ranges.iter().any(|&(low, high)| low <= c && c <= high) != *negated
+            }
+        }
+    }
+}
+
+/// A shell glob pattern (`*`, `?`, `[...]`, with `\` escaping the next
+/// character), matched against whole strings.
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if i + 1 < chars.len() => {
+                    tokens.push(Token::Char(chars[i + 1]));
+                    i += 1;
+                }
+                '*' => {
+                    if !matches!(tokens.last(), Some(Token::Star)) {
+                        tokens.push(Token::Star);
+                    }
+                }
+                '?' => tokens.push(Token::Any),
+                '[' => match parse_class(&chars[i + 1..]) {
+                    Some((token, consumed)) => {
+                        tokens.push(token);
+                        i += consumed;
+                    }
+                    None => tokens.push(Token::Char('[')),
+                },
+                c => tokens.push(Token::Char(c)),
+            }
+            i += 1;
+        }
+
+        Pattern { tokens }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn matches(&self, text: &[char]) -> bool {
+        let (mut t, mut p) = (0, 0);
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while t < text.len() {
+            match self.tokens.get(p) {
+                Some(Token::Star) => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                }
+                Some(token) if token.matches(text[t]) => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match backtrack {
+                    Some((star, matched)) => {
+                        p = star + 1;
+                        t = matched + 1;
+                        backtrack = Some((star, matched + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.tokens[p..].iter().all(|token| matches!(token, Token::Star))
+    }
+}
+
+/// Parses the body of a `[...]` bracket expression, returning the token and
+/// how many characters it used (including the closing bracket).
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let mut i = 0;
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let start = i;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == ']' && i > start {
+            return Some((Token::Class { ranges, negated }, i + 1));
+        }
+
+        let low = if c == '\\' && i + 1 < chars.len() {
+            i += 1;
+            chars[i]
+        } else {
+            c
+        };
+
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&high| high != ']') {
+            ranges.push((low, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((low, low));
+            i += 1;
+        }
+    }
+
+    None
+}
+
+/// Backslash-escapes glob characters so quoted text matches literally.
+pub fn escape_pattern(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::new(pattern).matches(&text.chars().collect::<Vec<char>>())
+    }
+
+    #[test]
+    fn wildcards_match_whole_strings() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(matches("f?o", "foo"));
+        assert!(!matches("f?o", "fo"));
+        assert!(matches("*", ""));
+        assert!(!matches("", "x"));
+    }
+
+    #[test]
+    fn backslash_makes_characters_literal() {
+        assert!(matches("a\\*", "a*"));
+        assert!(!matches("a\\*", "ab"));
+        assert!(matches(&escape_pattern("[x]?"), "[x]?"));
+    }
+
+    #[test]
+    fn bracket_expressions() {
+        assert!(matches("[abc]", "b"));
+        assert!(matches("[a-c]x", "cx"));
+        assert!(!matches("[!a-c]", "b"));
+        assert!(matches("[^a-c]", "d"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[a-]", "-"));
+        // An unclosed bracket is an ordinary character.
+        assert!(matches("[ab", "[ab"));
+    }
+
+    #[test]
+    fn parse_class_reports_what_it_consumed() {
+        let chars: Vec<char> = "!a-z_]rest".chars().collect();
+        let Some((Token::Class { ranges, negated }, consumed)) = parse_class(&chars) else {
+            panic!("expected a class");
+        };
+        assert!(negated);
+        assert_eq!(ranges, [('a', 'z'), ('_', '_')]);
+        assert_eq!(consumed, 6);
+        assert!(parse_class(&['a', 'b']).is_none());
+    }
+}
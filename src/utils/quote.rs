@@ -0,0 +1,9 @@
+/// Quotes a value so the shell reads it back unchanged, for output meant
+/// to be re-run like `set`, `declare -p` and `trap -p`. Plain words are
+/// left alone; anything else goes in single quotes.
+pub(crate) fn quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:,@%+=".contains(c)) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
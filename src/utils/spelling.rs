@@ -0,0 +1,72 @@
+/// Optimal string alignment distance: inserting, deleting or substituting a
+/// character, or swapping two adjacent ones, each cost one edit.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // rows[i][j] is the distance between the first i chars of a and the first j of b.
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Up to three candidates close enough to `word` to be what was meant,
+/// nearest first. Very short words match too much to be worth suggesting.
+pub fn closest_matches<'a>(word: &str, candidates: &'a [String]) -> Vec<&'a str> {
+    let length = word.chars().count();
+    let limit = match length {
+        0..=2 => return Vec::new(),
+        3..=4 => 1,
+        _ => 2,
+    };
+
+    let mut matches: Vec<(usize, &str)> = candidates.iter()
+        .filter(|candidate| candidate.chars().count().abs_diff(length) <= limit)
+        .map(|candidate| (edit_distance(word, candidate), candidate.as_str()))
+        .filter(|&(distance, _)| distance > 0 && distance <= limit)
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches.into_iter().take(3).map(|(_, candidate)| candidate).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_kind_of_edit_once() {
+        assert_eq!(edit_distance("git", "git"), 0);
+        assert_eq!(edit_distance("gti", "git"), 1);
+        assert_eq!(edit_distance("gt", "git"), 1);
+        assert_eq!(edit_distance("gitt", "git"), 1);
+        assert_eq!(edit_distance("got", "git"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggests_the_nearest_candidates() {
+        let candidates: Vec<String> = ["git", "grep", "echo", "exit", "gzip", "cargo"].iter().map(|name| name.to_string()).collect();
+        assert_eq!(closest_matches("gti", &candidates), ["git"]);
+        assert_eq!(closest_matches("ehco", &candidates), ["echo"]);
+        assert_eq!(closest_matches("carg", &candidates), ["cargo"]);
+        assert!(closest_matches("ls", &candidates).is_empty());
+        assert!(closest_matches("git", &candidates).is_empty());
+        assert!(closest_matches("xyzzy", &candidates).is_empty());
+    }
+}
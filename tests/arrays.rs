@@ -0,0 +1,21 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn indexed_arrays() {
+    let output = run(r#"a=(one "two three" four); echo ${#a[@]} ${a[1]} ${a[-1]} $a; a[5]=six; echo ${!a[@]}; printf '[%s]' "${a[@]}"; echo"#);
+    assert_eq!(output.stdout, "3 two three four one\n0 1 2 5\n[one][two three][four][six]\n");
+}
+
+#[test]
+fn associative_arrays() {
+    let output = run(r#"declare -A m; m[k]=v; m["x y"]=z; echo ${m[k]} ${m["x y"]} ${#m[@]}; printf '[%s]' "${!m[@]}" | grep -c 'x y'"#);
+    assert_eq!(output.stdout, "v z 2\n1\n");
+}
+
+#[test]
+fn declare_p_shows_arrays() {
+    let output = run(r#"a=(one "two three"); declare -a b=(1 2); declare -p a b"#);
+    assert_eq!(output.stdout, "declare -a a=([0]=one [1]='two three')\ndeclare -a b=([0]=1 [1]=2)\n");
+}
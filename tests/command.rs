@@ -0,0 +1,40 @@
+mod common;
+
+use common::{TempDir, run, shell_in};
+
+#[test]
+fn builtin_command_honours_redirections_and_pipes() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", "builtin command echo to-file > out; builtin command echo x | builtin command tr x y"], "");
+    assert_eq!(output.stdout, "y\n");
+    assert_eq!(dir.read("out"), "to-file\n");
+}
+
+#[test]
+fn command_skips_functions() {
+    let output = run("echo() { builtin echo wrapped \"$1\"; command echo real; }; echo arg");
+    assert_eq!(output.stdout, "wrapped arg\nreal\n");
+}
+
+#[test]
+fn command_v_reports_how_names_resolve() {
+    let output = run("f() { :; }; command -v f cd sh; command -v nosuch; echo $?");
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines[..2], ["f", "cd"]);
+    assert!(lines[2].ends_with("/sh"));
+    assert_eq!(lines[3], "1");
+}
+
+#[test]
+fn builtin_runs_only_builtins() {
+    let output = run("echo() { printf 'fn\\n'; }; builtin echo real; builtin nosuch; builtin echo $?");
+    assert_eq!(output.stdout, "real\n1\n");
+    assert!(output.stderr.contains("builtin: nosuch: not a shell builtin"));
+}
+
+#[test]
+fn command_p_and_capital_v() {
+    let output = run("command -p ls / >/dev/null && echo p-ok; command -V cd; command -V nosuch; echo $?");
+    assert_eq!(output.stdout, "p-ok\ncd is a shell builtin\n1\n");
+    assert!(output.stderr.contains("nosuch: not found"));
+}
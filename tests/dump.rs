@@ -0,0 +1,56 @@
+mod common;
+
+use common::{TempDir, shell, shell_in};
+
+#[test]
+fn dump_ast_prints_the_tree_without_running_anything() {
+    let output = shell(&["--dump-ast", "-c", "echo \"a b\" > out && ls &"]);
+    assert_eq!(
+        output.stdout,
+        "Script\n  Pipeline line=1\n    Command\n      Word\n        Literal text=\"echo\"\n      Word\n        Quoted text=\"a b\"\n      \
+         Redirect fd=1 operator=\">\"\n        Word\n          Literal text=\"out\"\n  Pipeline line=1 background run_if=\"&&\"\n    \
+         Command\n      Word\n        Literal text=\"ls\"\n"
+    );
+    assert_eq!(output.status, 0);
+}
+
+#[test]
+fn dump_ast_json() {
+    let output = shell(&["--dump-ast=json", "-c", "echo hi"]);
+    assert!(output.stdout.starts_with("{\n  \"type\": \"Script\",\n  \"children\": ["));
+    assert!(output.stdout.contains("\"text\": \"hi\""));
+
+    let output = shell(&["--dump-ast=xml", "-c", "echo hi"]);
+    assert!(output.stderr.contains("--dump-ast=xml: format must be `text' or `json'"));
+    assert_eq!(output.status, 2);
+}
+
+#[test]
+fn noexec_only_parses() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-n", "-c", "echo hi > created"], "");
+    assert!(output.stdout.starts_with("Script\n"));
+    assert!(!dir.path().join("created").exists());
+}
+
+#[test]
+fn format_reads_scripts_from_stdin() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["--format"], "echo   hi|cat>o;ls&&pwd\n\nx=( 1  \"2 3\" )\n");
+    assert_eq!(output.stdout, "echo hi | cat > o; ls && pwd\nx=(1 \"2 3\")\n");
+}
+
+#[test]
+fn syntax_errors_fail_the_dump() {
+    for (script, message) in [
+        ("echo a | | b", "rust-shell: line 1: Syntax error: unexpected `|'\n"),
+        ("echo ok\necho \"open", "rust-shell: line 2: Syntax error: unexpected end of input while looking for matching `\"'\n"),
+    ] {
+        for flag in ["-n", "--dump-ast=json", "--format"] {
+            let output = shell(&[flag, "-c", script]);
+            assert_eq!(output.stdout, "");
+            assert_eq!(output.stderr, message);
+            assert_eq!(output.status, 2);
+        }
+    }
+}
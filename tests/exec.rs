@@ -0,0 +1,27 @@
+mod common;
+
+use common::{TempDir, run, shell_in};
+
+#[test]
+fn exec_redirections_last_for_the_shell() {
+    let dir = TempDir::new();
+    let script = "exec 3>log; echo to3 >&3; exec 3>&-; echo x >&3; echo $?; exec 2>errs; ls /nonexistent; exec 2>&1; wc -l < errs";
+    let output = shell_in(dir.path(), &["-c", script], "");
+    assert_eq!(output.stdout, "1\n1\n");
+    assert_eq!(dir.read("log"), "to3\n");
+}
+
+#[test]
+fn exec_replaces_the_shell_after_the_exit_trap() {
+    let output = run(r#"trap "echo exit trap" EXIT; exec sh -c 'echo replaced; exit 4'; echo unreachable"#);
+    assert_eq!(output.stdout, "exit trap\nreplaced\n");
+    assert_eq!(output.status, 4);
+}
+
+#[test]
+fn failed_exec_ends_a_script() {
+    let output = run("exec nosuchcmd; echo still");
+    assert_eq!(output.stdout, "");
+    assert!(output.stderr.contains("exec: nosuchcmd: not found"));
+    assert_eq!(output.status, 127);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn hash_remembers_and_forgets_lookups() {
+    let output = run("hash; ls >/dev/null; ls / >/dev/null; hash; hash -t ls; hash -r; hash; hash nosuch; echo $?");
+    let ls = String::from_utf8(std::process::Command::new("sh").args(["-c", "command -v ls"]).output().unwrap().stdout).unwrap();
+    let empty = "hash: hash table empty\n";
+    assert_eq!(output.stdout, format!("{empty}hits\tcommand\n   2\t{ls}{ls}{empty}1\n"));
+    assert!(output.stderr.contains("hash: nosuch: not found"));
+}
+
+#[test]
+fn hash_p_names_a_path() {
+    let output = run("hash -p /bin/echo myecho; myecho via-p; hash -t myecho");
+    assert_eq!(output.stdout, "via-p\n/bin/echo\n");
+}
+
+#[test]
+fn cache_follows_path_and_missing_binaries() {
+    let script = r#"mkdir one two; printf '#!/bin/sh\necho one\n' > one/tool; printf '#!/bin/sh\necho two\n' > two/tool; chmod +x one/tool two/tool;
+PATH="$(pwd)/one:$(pwd)/two:$PATH"; tool; rm one/tool; tool; hash -t tool | grep -c two; PATH=$PATH; hash"#;
+    let output = run(script);
+    assert_eq!(output.stdout, "one\ntwo\n1\nhash: hash table empty\n");
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::{TempDir, run};
+
+#[test]
+fn background_pipelines_run_concurrently() {
+    let started = Instant::now();
+    let output = run("sleep 0.3 & sleep 0.3 & echo started; wait; echo waited $?");
+    assert_eq!(output.stdout, "started\nwaited 0\n");
+    assert!(started.elapsed() < Duration::from_millis(550));
+}
+
+#[test]
+fn jobs_lists_background_jobs() {
+    let output = run("sleep 0.2 & sleep 0.2 | cat & jobs; jobs -p | wc -l");
+    assert_eq!(output.stdout, "[1]-  Running                 sleep 0.2 &\n[2]+  Running                 sleep 0.2 | cat &\n2\n");
+}
+
+#[test]
+fn wait_reports_the_job_status() {
+    let output = run(r#"sh -c "exit 3" & wait $!; echo $?; sleep 5 & kill $!; wait %1; echo $?"#);
+    assert_eq!(output.stdout, "3\n143\n");
+}
+
+#[test]
+fn background_pid_is_the_command_itself() {
+    let started = Instant::now();
+    let output = run(r#"sh -c 'echo $$' & echo $!; wait; sleep 5 & kill $!; wait $!; echo $?; f() { sleep 0.1; echo body; }; f & wait"#);
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines[0], lines[1]);
+    assert_eq!(lines[2..], ["143", "body"]);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn job_specs_and_disown() {
+    let output = run("sleep 0.2 & sleep 0.2 & disown %1; jobs; jobs %sl; jobs %?0.2; jobs %x; echo $?; disown %5; echo $?");
+    assert_eq!(output.stdout, "[2]+  Running                 sleep 0.2 &\n".repeat(3) + "1\n1\n");
+    assert!(output.stderr.contains("jobs: %x: no such job"));
+    assert!(output.stderr.contains("disown: %5: no such job"));
+}
+
+#[test]
+fn fg_and_bg_need_job_control() {
+    let output = run("fg; echo $?; bg; echo $?");
+    assert_eq!(output.stdout, "1\n1\n");
+    assert!(output.stderr.contains("fg: no job control"));
+    assert!(output.stderr.contains("bg: no job control"));
+}
+
+#[test]
+fn wait_for_unknown_jobs_and_processes() {
+    let output = run("wait %9; echo $?; wait 1; echo $?; wait xx; echo $?; sleep 0.1 & wait %5 $!; echo $?");
+    assert_eq!(output.stdout, "127\n127\n1\n0\n");
+    assert!(output.stderr.contains("wait: %9: no such job"));
+    assert!(output.stderr.contains("wait: pid 1 is not a child of this shell"));
+    assert!(output.stderr.contains("wait: `xx': not a pid or valid job spec"));
+}
+
+/// Types `lines` into an interactive shell on a terminal, which `script`
+/// provides, pausing after each one.
+fn interactive(dir: &TempDir, lines: &[(&str, u64)]) {
+    let mut child = Command::new("script")
+        .args(["-qec", env!("CARGO_BIN_EXE_codecrafters-shell"), "/dev/null"])
+        .current_dir(dir.path())
+        .env("HOME", dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for (line, pause) in lines {
+        writeln!(stdin, "{}", line).unwrap();
+        thread::sleep(Duration::from_millis(*pause));
+    }
+    drop(stdin);
+    child.wait().unwrap();
+}
+
+#[test]
+fn fg_reports_on_its_own_stderr() {
+    let dir = TempDir::new();
+    interactive(&dir, &[
+        ("sh -c 'sleep 0.3; kill -TERM $$' &", 100),
+        ("fg 2>err; echo $? >status", 800),
+        ("exit", 200),
+    ]);
+    assert_eq!(dir.read("err"), "Terminated\n");
+    assert_eq!(dir.read("status"), "143\n");
+}
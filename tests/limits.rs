@@ -0,0 +1,31 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn umask_reads_and_sets_the_mask() {
+    let output = run("umask 022; umask; umask -S; umask u=rwx,g=rx,o=; umask; umask 10000; echo $?; umask; umask 7777; umask; umask a+q; echo $?");
+    assert_eq!(output.stdout, "0022\nu=rwx,g=rx,o=rx\n0027\n1\n0027\n0777\n1\n");
+    assert!(output.stderr.contains("umask: 10000: octal number out of range"));
+    assert!(output.stderr.contains("umask: `q': invalid symbolic mode character"));
+}
+
+#[test]
+fn umask_applies_to_new_files() {
+    let output = run("umask 077; > file; ls -l file | cut -c1-10");
+    assert_eq!(output.stdout, "-rw-------\n");
+}
+
+#[test]
+fn ulimit_sets_soft_and_hard_limits() {
+    let output = run("ulimit -n 100; ulimit -n; ulimit -S -n; ulimit -H -n; ulimit -n 200; echo $?");
+    assert_eq!(output.stdout, "100\n100\n100\n1\n");
+    assert!(output.stderr.contains("ulimit: open files: cannot modify limit"));
+}
+
+#[test]
+fn ulimit_rejects_unknown_options() {
+    let output = run("ulimit -Q; echo $?");
+    assert_eq!(output.stdout, "2\n");
+    assert!(output.stderr.contains("ulimit: -Q: invalid option"));
+}
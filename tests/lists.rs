@@ -0,0 +1,10 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn and_or_before_a_function_definition() {
+    let output = run("true || f() { echo hi; }; echo after; f; false && g() { :; }; echo ran");
+    assert_eq!(output.stdout, "after\nran\n");
+    assert!(output.stderr.contains("f: command not found"));
+}
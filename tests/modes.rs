@@ -0,0 +1,81 @@
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::{TempDir, shell_in};
+
+#[test]
+fn runs_a_script_with_arguments() {
+    let dir = TempDir::new();
+    dir.write("s.sh", "echo \"script $0 $1 $2\"\nexit 3\necho unreachable\n");
+    let output = shell_in(dir.path(), &["s.sh", "a", "b"], "");
+    assert_eq!(output.stdout, "script s.sh a b\n");
+    assert_eq!(output.status, 3);
+}
+
+#[test]
+fn missing_script() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["missing.sh"], "");
+    assert!(output.stderr.contains("missing.sh"));
+    assert_eq!(output.status, 127);
+}
+
+#[test]
+fn command_string_exit_status() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", "echo $0 $1; false", "name", "arg"], "");
+    assert_eq!(output.stdout, "name arg\n");
+    assert_eq!(output.status, 1);
+}
+
+#[test]
+fn reads_commands_from_stdin_without_a_prompt() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &[], "echo one\necho two; exit 5\n");
+    assert_eq!(output.stdout, "one\ntwo\n");
+    assert_eq!(output.status, 5);
+
+    let output = shell_in(dir.path(), &["-s", "p", "q"], "echo $1 $#\n");
+    assert_eq!(output.stdout, "p 2\n");
+}
+
+#[test]
+fn runs_shebang_scripts() {
+    let dir = TempDir::new();
+    let script = dir.write("tool", &format!("#!{}\necho shebang $1\n", env!("CARGO_BIN_EXE_codecrafters-shell")));
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    // Another test forking at the wrong moment can briefly hold the file
+    // open for writing.
+    let output = (0..50)
+        .find_map(|_| match std::process::Command::new(&script).arg("x").output() {
+            Err(e) if e.raw_os_error() == Some(libc::ETXTBSY) => {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                None
+            }
+            result => Some(result.unwrap()),
+        })
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "shebang x\n");
+}
+
+#[test]
+fn syntax_errors_exit_with_status_2() {
+    let dir = TempDir::new();
+    for script in ["echo a &&", "echo a >", "echo a | | b", "echo \"open"] {
+        let output = shell_in(dir.path(), &["-c", script], "");
+        assert_eq!(output.stdout, "", "{}", script);
+        assert!(output.stderr.contains("Syntax error"), "{}", script);
+        assert_eq!(output.status, 2, "{}", script);
+    }
+
+    dir.write("s.sh", "echo one\necho a | | b\necho two\n");
+    let output = shell_in(dir.path(), &["s.sh"], "");
+    assert_eq!(output.stdout, "one\n");
+    assert_eq!(output.stderr, "s.sh: line 2: Syntax error: unexpected `|'\n");
+    assert_eq!(output.status, 2);
+
+    let output = shell_in(dir.path(), &["-s"], "trap 'echo exit $?' EXIT\necho one\necho a &&\n");
+    assert_eq!(output.stdout, "one\nexit 2\n");
+    assert_eq!(output.status, 2);
+}
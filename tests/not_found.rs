@@ -0,0 +1,21 @@
+mod common;
+
+use common::run;
+
+const SETUP: &str = r#"mkdir bin; printf '#!/bin/sh\n' > bin/git; chmod +x bin/git; PATH="$(pwd)/bin"; "#;
+
+#[test]
+fn suggests_close_commands() {
+    let output = run(&format!("{SETUP}gti; echo $?; ehco; zzzzzz"));
+    assert_eq!(output.stdout, "127\n");
+    assert!(output.stderr.contains("gti: command not found. Did you mean: git?"));
+    assert!(output.stderr.contains("ehco: command not found. Did you mean: echo?"));
+    assert!(output.stderr.contains("zzzzzz: command not found\n"));
+}
+
+#[test]
+fn calls_command_not_found_handle() {
+    let output = run(&format!(r#"{SETUP}command_not_found_handle() {{ echo "handled $1 $#"; return 5; }}; gti a b; echo $?; git; echo $?"#));
+    assert_eq!(output.stdout, "handled gti 3\n5\n0\n");
+    assert_eq!(output.stderr, "");
+}
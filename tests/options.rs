@@ -0,0 +1,43 @@
+mod common;
+
+use common::{TempDir, run, shell_in};
+
+#[test]
+fn errexit_stops_at_an_unchecked_failure() {
+    let output = run("set -e; false || echo handled; ! false; true && false && echo no; echo still; false; echo unreachable");
+    assert_eq!(output.stdout, "handled\nstill\n");
+    assert_eq!(output.status, 1);
+}
+
+#[test]
+fn nounset_rejects_unset_variables() {
+    let output = run("set -u; echo ${missing}; echo unreachable");
+    assert_eq!(output.stdout, "");
+    assert!(output.stderr.contains("missing: unbound variable"));
+    assert_eq!(output.status, 1);
+}
+
+#[test]
+fn xtrace_prints_expanded_commands() {
+    let output = run("set -x; x=1; echo $x");
+    assert_eq!(output.stdout, "1\n");
+    assert_eq!(output.stderr, "+ x=1\n+ echo 1\n");
+}
+
+#[test]
+fn set_o_lists_and_changes_options() {
+    let output = run("set -o | grep pipefail; set -o pipefail; set -o | grep pipefail");
+    assert_eq!(output.stdout, "pipefail       \toff\npipefail       \ton\n");
+}
+
+#[test]
+fn glob_options() {
+    let dir = TempDir::new();
+    for name in ["a.rs", "b.rs", ".hidden.rs", "README"] {
+        dir.write(name, "");
+    }
+    let script = "echo *.rs; echo \\*.rs '*.rs' none*; shopt -s dotglob; echo *.rs; shopt -u dotglob; \
+                  shopt -s nocaseglob; echo read*; set -f; echo *.rs";
+    let output = shell_in(dir.path(), &["-c", script], "");
+    assert_eq!(output.stdout, "a.rs b.rs\n*.rs *.rs none*\n.hidden.rs a.rs b.rs\nREADME\n*.rs\n");
+}
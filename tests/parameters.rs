@@ -0,0 +1,29 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn defaults_and_alternatives() {
+    let output = run(r#"e=; x=set; echo "${u:-def} ${e:-empty} [${e-unused}] ${x:+alt} ${u:=assigned} $u"; echo "${v:?missing}"; echo unreachable"#);
+    assert_eq!(output.stdout, "def empty [] alt assigned assigned\n");
+    assert!(output.stderr.contains("v: missing"));
+    assert_eq!(output.status, 1);
+}
+
+#[test]
+fn patterns_apply_inside_double_quotes() {
+    let output = run(r#"x=hello.tar.gz; p='*.'; echo "${x#*.} ${x##*.} ${x%.*} ${x%%.*} ${x#$p} ${x#"*."}""#);
+    assert_eq!(output.stdout, "tar.gz gz hello.tar hello tar.gz hello.tar.gz\n");
+}
+
+#[test]
+fn replacement_substring_length_and_case() {
+    let output = run(r#"x=hello; echo "${x/l/L} ${x//l/L} ${x//[lo]/_} ${x:1:3} ${x: -2} ${#x} ${x^^} ${x^}""#);
+    assert_eq!(output.stdout, "heLlo heLLo he___ ell lo 5 HELLO Hello\n");
+}
+
+#[test]
+fn empty_anchored_patterns_add_a_prefix_or_suffix() {
+    let output = run(r#"x=foo; arr=(a b); echo ${x/#/pre} ${x/%/suf} ${arr[@]/#/-I} ${x//}"#);
+    assert_eq!(output.stdout, "prefoo foosuf -Ia -Ib foo\n");
+}
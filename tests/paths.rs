@@ -0,0 +1,22 @@
+mod common;
+
+use common::run;
+
+// The scripts are written by the shell itself rather than the test, so no
+// other test's fork can be holding them open for writing when they run.
+const SETUP: &str = r#"mkdir bin dir; printf 'echo noshebang $1\n' > ns; printf '#!/bin/sh\necho shebang $0\n' > bin/tool; printf 'x\n' > noexec; chmod +x ns bin/tool; "#;
+
+#[test]
+fn runs_commands_given_by_path() {
+    let output = run(&format!("{SETUP}./ns a; bin/tool; /usr/bin/env echo env"));
+    assert_eq!(output.stdout, "noshebang a\nshebang bin/tool\nenv\n");
+}
+
+#[test]
+fn reports_paths_that_cannot_run() {
+    let output = run(&format!("{SETUP}./noexec; echo $?; ./dir; echo $?; ./missing; echo $?"));
+    assert_eq!(output.stdout, "126\n126\n127\n");
+    assert!(output.stderr.contains("./noexec: Permission denied"));
+    assert!(output.stderr.contains("./dir: Is a directory"));
+    assert!(output.stderr.contains("./missing: No such file or directory"));
+}
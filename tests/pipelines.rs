@@ -0,0 +1,27 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{TempDir, run, shell_in};
+
+#[test]
+fn builtins_stream_into_the_next_stage() {
+    let dir = TempDir::new();
+    let script = r#"seq 1 50000 > big; mapfile -t a < big; echo "${a[@]}" | head -c 10; echo; echo "${a[@]}" | wc -c; declare -p a | tail -c 9"#;
+    let output = shell_in(dir.path(), &["-c", script], "");
+    assert_eq!(output.stdout, "1 2 3 4 5 \n288894\n]=50000)\n");
+}
+
+#[test]
+fn builtin_stages_run_alongside_the_others() {
+    let started = Instant::now();
+    let output = run("type echo | cat; pwd | wc -l; echo first | sleep 0.3 | cat; echo done");
+    assert_eq!(output.stdout, "echo is a shell builtin\n1\ndone\n");
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn builtin_output_reaches_a_slow_reader() {
+    let output = run("echo data | sh -c 'sleep 0.2; cat'");
+    assert_eq!(output.stdout, "data\n");
+}
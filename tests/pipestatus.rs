@@ -0,0 +1,15 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn pipestatus_holds_every_stage() {
+    let output = run(r#"false | true | sh -c "exit 3"; echo "${PIPESTATUS[@]} $?"; echo x | type nosuch 2>/dev/null | cat; echo "${PIPESTATUS[@]}"; true; echo "${PIPESTATUS[@]}""#);
+    assert_eq!(output.stdout, "1 0 3 3\n0 1 0\n0\n");
+}
+
+#[test]
+fn pipefail_reports_the_last_failure() {
+    let output = run(r#"false | true; echo $?; set -o pipefail; false | true; echo $?; sh -c "exit 2" | false | true; echo $?; true | true; echo $?; ! false | false; echo $?"#);
+    assert_eq!(output.stdout, "0\n1\n1\n0\n0\n");
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::{TempDir, shell_in};
+
+#[test]
+fn redirections_apply_in_order() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", "ls /nonexist 2>&1 >file; echo err >&2 2>/dev/null"], "");
+    assert_eq!(dir.read("file"), "");
+    assert!(output.stdout.contains("/nonexist"));
+    assert_eq!(output.stderr, "err\n");
+}
+
+#[test]
+fn builtin_redirections_apply_in_order() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", "type nosuch 2>&1 >f; type nosuch >g 2>&1"], "");
+    assert_eq!(dir.read("f"), "");
+    assert!(output.stdout.contains("nosuch: not found"));
+    assert!(dir.read("g").contains("nosuch: not found"));
+}
+
+#[test]
+fn builtins_read_and_write_through_redirections() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", "echo hi > f; read x < f; echo \"[$x]\"; cd /nonexist 2>errs; echo $?"], "");
+    assert_eq!(output.stdout, "[hi]\n1\n");
+    assert!(dir.read("errs").contains("/nonexist"));
+}
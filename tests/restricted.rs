@@ -0,0 +1,52 @@
+mod common;
+
+use common::{TempDir, shell};
+
+#[test]
+fn builtin_command_cannot_run_a_path() {
+    let output = shell(&["-r", "-c", "builtin command /bin/echo escaped; echo status $?"]);
+    assert_eq!(output.stdout, "status 1\n");
+    assert!(output.stderr.contains("/bin/echo: restricted: cannot specify `/' in command names"));
+}
+
+#[test]
+fn declare_cannot_replace_path() {
+    let output = shell(&["-r", "-c", "declare -a PATH; echo status $?; declare -A SHELL; env | grep -c '^PATH='"]);
+    assert_eq!(output.stdout, "status 1\n1\n");
+    assert!(output.stderr.contains("PATH: readonly variable"));
+    assert!(output.stderr.contains("SHELL: readonly variable"));
+}
+
+#[test]
+fn restricted_shell_refuses_each_escape() {
+    let output = shell(&["-r", "-c", "cd /; echo $?; /bin/echo x; echo $?; echo hi > out; echo $?; echo hi >> out; exec ls; echo $?; ls out; echo ok >&2"]);
+    assert_eq!(output.stdout, "1\n1\n1\n1\n");
+    for message in [
+        "cd: restricted",
+        "/bin/echo: restricted: cannot specify `/' in command names",
+        "out: restricted: cannot redirect output",
+        "exec: restricted",
+        "No such file or directory",
+    ] {
+        assert!(output.stderr.contains(message), "missing {message:?} in {}", output.stderr);
+    }
+    assert!(output.stderr.ends_with("ok\n"));
+}
+
+#[test]
+fn restrictions_cannot_be_lifted() {
+    let output = shell(&["-r", "-c", "PATH=/x; SHELL=x; ENV=y; set +r; echo $?; set +o restricted; echo $?; echo $-"]);
+    assert_eq!(output.stdout, "2\n2\nrc\n");
+    for name in ["PATH", "SHELL", "ENV"] {
+        assert!(output.stderr.contains(&format!("{name}: readonly variable")));
+    }
+}
+
+#[test]
+fn invoked_as_rrust_shell() {
+    let dir = TempDir::new();
+    std::os::unix::fs::symlink(env!("CARGO_BIN_EXE_codecrafters-shell"), dir.path().join("rrust-shell")).unwrap();
+    let output = std::process::Command::new(dir.path().join("rrust-shell")).args(["-c", "echo $-; cd /"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "rc\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cd: restricted"));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use common::run;
+
+#[test]
+fn reports_statuses_of_signalled_children() {
+    let output = run(r#"sh -c 'kill -INT $$'; echo $?; sh -c 'kill -TERM $$'; echo $?; sh -c 'kill -QUIT $$'; echo $?"#);
+    assert_eq!(output.stdout, "130\n143\n131\n");
+    assert!(output.stderr.contains("Terminated"));
+    assert!(output.stderr.contains("Quit"));
+}
+
+#[test]
+fn survives_sigint_sent_only_to_itself_while_a_job_runs() {
+    let output = run(r#"sh -c 'sleep 0.1; kill -INT $PPID; sleep 0.1'; echo survived"#);
+    assert_eq!(output.stdout, "survived\n");
+}
+
+/// Runs `script` in its own process group and sends the whole group SIGINT
+/// while `sleep` is in the foreground, as Ctrl-C would. The shell starts
+/// with SIGINT at its default even when the tests were started with it
+/// ignored, in the background of a script.
+fn interrupt_group(script: &str) -> (String, Option<i32>) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_codecrafters-shell"));
+    command.args(["-c", script]).process_group(0).stdout(Stdio::piped());
+    unsafe {
+        command.pre_exec(|| {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            Ok(())
+        });
+    }
+    let child = command.spawn().unwrap();
+    thread::sleep(Duration::from_millis(300));
+    unsafe { libc::killpg(child.id() as i32, libc::SIGINT) };
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8_lossy(&output.stdout).into_owned(), output.status.signal())
+}
+
+#[test]
+fn ctrl_c_stops_a_script() {
+    let (stdout, signal) = interrupt_group("trap 'echo exit trap' EXIT; sleep 2; echo after");
+    assert_eq!(stdout, "exit trap\n");
+    assert_eq!(signal, Some(libc::SIGINT));
+}
+
+#[test]
+fn trapped_sigint_lets_the_script_continue() {
+    let (stdout, signal) = interrupt_group("trap 'echo caught' INT; sleep 2; echo after");
+    assert_eq!(stdout, "caught\nafter\n");
+    assert_eq!(signal, None);
+}
+
+/// The ignored-signal mask `grep` reports for itself when run by `script`.
+fn ignored_mask(script: &str) -> u64 {
+    let output = run(script);
+    let mask = output.stdout.trim().strip_prefix("SigIgn:").expect("a SigIgn line").trim();
+    u64::from_str_radix(mask, 16).unwrap()
+}
+
+#[test]
+fn commands_inherit_signals_ignored_by_trap() {
+    let bit = |signal: i32| 1u64 << (signal - 1);
+    assert_eq!(ignored_mask("grep SigIgn /proc/self/status") & bit(libc::SIGINT), 0);
+    assert_ne!(ignored_mask("trap '' INT; grep SigIgn /proc/self/status") & bit(libc::SIGINT), 0);
+    assert_ne!(ignored_mask("trap '' QUIT; exec grep SigIgn /proc/self/status") & bit(libc::SIGQUIT), 0);
+    assert_eq!(ignored_mask("trap '' INT; trap - INT; grep SigIgn /proc/self/status") & bit(libc::SIGINT), 0);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{TempDir, shell_in};
+
+#[test]
+fn sources_into_the_current_shell() {
+    let dir = TempDir::new();
+    dir.write("lib.sh", "x=from_file; echo \"args $# $1\"\nreturn 4\necho unreachable\n");
+    let output = shell_in(dir.path(), &["-c", r#"set -- outer; source ./lib.sh a; echo "$? $x $1"; . ./lib.sh; echo $?"#], "");
+    assert_eq!(output.stdout, "args 1 a\n4 from_file outer\nargs 1 outer\n4\n");
+}
+
+#[test]
+fn searches_path_for_plain_names() {
+    let dir = TempDir::new();
+    std::fs::create_dir(dir.path().join("bin")).unwrap();
+    dir.write("bin/onpath.sh", "echo found on path\n");
+    let script = format!("PATH={}/bin:$PATH; . onpath.sh", dir.path().display());
+    let output = shell_in(dir.path(), &["-c", &script], "");
+    assert_eq!(output.stdout, "found on path\n");
+}
+
+#[test]
+fn missing_files() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", "source missing.sh; echo $?; source; echo $?"], "");
+    assert_eq!(output.stdout, "1\n1\n");
+    assert!(output.stderr.contains("missing.sh"));
+    assert!(output.stderr.contains("source: filename argument required"));
+}
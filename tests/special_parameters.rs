@@ -0,0 +1,33 @@
+mod common;
+
+use common::{run, shell};
+
+#[test]
+fn positional_parameters_and_counts() {
+    let output = shell(&["-c", r#"echo "$# $1 $2 $0"; echo "$*"; echo $@"#, "name", "x", "y"]);
+    assert_eq!(output.stdout, "2 x y name\nx y\nx y\n");
+}
+
+#[test]
+fn process_ids() {
+    let output = run(r#"echo $$; sh -c 'echo $PPID'; [ "$(echo $$)" = "$$" ] && echo substitution; sleep 0 & [ "$!" -gt 0 ] && echo background"#);
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines[0], lines[1]);
+    assert_eq!(lines[2..], ["substitution", "background"]);
+}
+
+#[test]
+fn subshells_keep_the_shells_ppid() {
+    let output = run(r#"echo $PPID; echo $(echo $PPID); f() { echo $PPID; }; f | cat"#);
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], lines[1]);
+    assert_eq!(lines[0], lines[2]);
+    assert_eq!(lines[0], std::process::id().to_string());
+}
+
+#[test]
+fn status_last_argument_and_flags() {
+    let output = run("false; echo $?; echo a b c; echo $_; echo $-");
+    assert_eq!(output.stdout, "1\na b c\nc\nc\n");
+}
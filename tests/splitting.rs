@@ -0,0 +1,31 @@
+mod common;
+
+use common::run;
+
+const ARGS: &str = r#"args() { echo "$#:$(printf '[%s]' "$@")"; }; "#;
+
+fn run_args(script: &str) -> String {
+    run(&format!("{ARGS}{script}")).stdout
+}
+
+#[test]
+fn unquoted_expansions_split_on_whitespace() {
+    assert_eq!(run_args(r#"x="  a  b  "; args $x; args "$x"; e=; args $e "$e""#), "2:[a][b]\n1:[  a  b  ]\n1:[]\n");
+}
+
+#[test]
+fn custom_ifs() {
+    assert_eq!(run_args(r#"IFS=:; y="a::b:"; args $y; IFS=" :"; z=" a : b "; args $z"#), "3:[a][][b]\n2:[a][b]\n");
+    assert_eq!(run_args(r#"IFS=; x=" a b"; args $x"#), "1:[ a b]\n");
+}
+
+#[test]
+fn command_substitution_is_split() {
+    assert_eq!(run_args(r#"args $(printf 'one two\nthree'); args "$(echo a b)""#), "3:[one][two][three]\n1:[a b]\n");
+}
+
+#[test]
+fn positional_parameters() {
+    let output = run_args(r#"set -- "p 1" p2; args "$@"; args $@; args "$*"; IFS=-; args "$*""#);
+    assert_eq!(output, "2:[p 1][p2]\n3:[p][1][p2]\n1:[p 1 p2]\n1:[p 1-p2]\n");
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::{TempDir, shell_with_env};
+
+fn home() -> TempDir {
+    let home = TempDir::new();
+    home.write(".rustshell_profile", "echo profile ran; x=1\n");
+    home.write(".rustshellrc", "echo rc ran\n");
+    home
+}
+
+#[test]
+fn login_shells_read_the_profile() {
+    let home = home();
+    let home_path = home.path().to_str().unwrap();
+    let output = shell_with_env(home.path(), &["-l", "-c", "echo x=$x"], "", &[("HOME", home_path)]);
+    assert!(output.stdout.ends_with("profile ran\nx=1\n"), "{}", output.stdout);
+    assert!(!output.stdout.contains("rc ran"));
+}
+
+#[test]
+fn noprofile_and_non_login_shells_skip_it() {
+    let home = home();
+    let home_path = home.path().to_str().unwrap();
+    let output = shell_with_env(home.path(), &["--login", "--noprofile", "-c", "echo x=$x"], "", &[("HOME", home_path)]);
+    assert_eq!(output.stdout, "x=\n");
+    let output = shell_with_env(home.path(), &["-c", "echo x=$x"], "", &[("HOME", home_path)]);
+    assert_eq!(output.stdout, "x=\n");
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::{TempDir, shell_in};
+
+#[test]
+fn builtins_read_from_the_previous_stage() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", r#"printf 'a b c\n' | read x rest; echo "$x|$rest"; printf 'l1\nl2\n' | mapfile -t b; echo ${#b[@]} ${b[1]}"#], "");
+    assert_eq!(output.stdout, "a|b c\n2 l2\n");
+}
+
+#[test]
+fn builtins_read_from_input_redirections() {
+    let dir = TempDir::new();
+    dir.write("f", "l1\nl2\n");
+    let output = shell_in(dir.path(), &["-c", "mapfile -t arr < f; echo ${#arr[@]} ${arr[1]}; read -r line < f; echo $line"], "");
+    assert_eq!(output.stdout, "2 l2\nl1\n");
+}
+
+#[test]
+fn builtins_read_the_shells_stdin() {
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &["-c", r#"read first; read second; echo "$second $first""#], "one\ntwo\n");
+    assert_eq!(output.stdout, "two one\n");
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::run;
+
+const BUSY: &str = "awk 'BEGIN { for (i = 0; i < 5000000; i++) x += i }'";
+
+#[test]
+fn time_counts_only_the_timed_pipeline() {
+    let output = run(&format!("TIMEFORMAT=%1U; {BUSY} & time wait; time {BUSY} | cat"));
+    let mut reports = output.stderr.lines();
+    assert_eq!(reports.next(), Some("0.0"));
+    let busy: f64 = reports.next().unwrap().parse().unwrap();
+    assert!(busy > 0.0);
+}
+
+#[test]
+fn time_p_uses_the_posix_format() {
+    let output = run("time -p true");
+    let lines: Vec<&str> = output.stderr.lines().map(|line| line.split(' ').next().unwrap()).collect();
+    assert_eq!(lines, ["real", "user", "sys"]);
+}
+
+#[test]
+fn timeformat_controls_the_report() {
+    let output = run("TIMEFORMAT='r=%1R %%'; time sleep 0.1; TIMEFORMAT=%Z; time true; echo $?; TIMEFORMAT=; time true");
+    assert_eq!(output.stdout, "0\n");
+    assert_eq!(output.stderr.lines().next(), Some("r=0.1 %"));
+    assert!(output.stderr.contains("TIMEFORMAT: `Z': invalid format character"));
+}
+
+#[test]
+fn times_prints_shell_and_child_usage() {
+    let output = run("times");
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.split(' ').all(|field| field.ends_with('s') && field.contains('m'))));
+}
+
+#[test]
+fn time_may_follow_negation() {
+    let output = run("TIMEFORMAT=timed; ! time true; echo $?; time ! true; echo $?");
+    assert_eq!(output.stdout, "1\n1\n");
+    assert_eq!(output.stderr, "timed\ntimed\n");
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{TempDir, run, shell_in};
+
+#[test]
+fn trap_p_lists_and_resets_traps() {
+    let output = run(r#"trap "echo bye" EXIT; trap 'echo "$?"' ERR; trap "echo usr1" USR1; trap "" TERM; trap -p; trap - USR1 TERM; trap -p USR1 TERM"#);
+    assert_eq!(
+        output.stdout,
+        "trap -- 'echo bye' EXIT\ntrap -- 'echo usr1' SIGUSR1\ntrap -- '' SIGTERM\ntrap -- 'echo \"$?\"' ERR\nbye\n"
+    );
+}
+
+#[test]
+fn signal_err_and_exit_traps_run() {
+    let output = run(r#"trap "echo bye" EXIT; trap 'echo err $?' ERR; trap "echo usr1" USR1; false; kill -USR1 $$; echo after"#);
+    assert_eq!(output.stdout, "err 1\nusr1\nafter\nbye\n");
+    assert_eq!(output.status, 0);
+}
+
+#[test]
+fn exit_trap_runs_at_exit_and_end_of_input() {
+    let output = run(r#"trap 'echo bye $?' EXIT; exit 3"#);
+    assert_eq!(output.stdout, "bye 3\n");
+    assert_eq!(output.status, 3);
+
+    let dir = TempDir::new();
+    let output = shell_in(dir.path(), &[], "trap 'echo bye' EXIT\necho last\n");
+    assert_eq!(output.stdout, "last\nbye\n");
+}
+
+#[test]
+fn trap_l_and_invalid_signals() {
+    let output = run("trap -l | head -1; trap 'echo x' BOGUS; echo $?");
+    assert_eq!(output.stdout, " 1) SIGHUP\t 2) SIGINT\t 3) SIGQUIT\t 4) SIGILL\t 5) SIGTRAP\n1\n");
+    assert!(output.stderr.contains("trap: BOGUS: invalid signal specification"));
+}
+
+#[test]
+fn return_trap_runs_for_source_and_functions_that_set_it() {
+    let dir = TempDir::new();
+    dir.write("lib.sh", "echo sourced\n");
+    let script = r#"trap "echo ret" RETURN; g() { echo in g; }; g; . ./lib.sh; f() { trap 'echo ret f $1' RETURN; return 3; }; f a; echo $?"#;
+    let output = shell_in(dir.path(), &["-c", script], "");
+    assert_eq!(output.stdout, "in g\nsourced\nret\nret f a\n3\n");
+}
+
+#[test]
+fn debug_trap_runs_before_each_command() {
+    let output = run(r#"trap 'echo debug' DEBUG; echo one; echo two"#);
+    assert_eq!(output.stdout, "debug\none\ndebug\ntwo\n");
+}
+
+#[test]
+fn return_trap_set_in_a_function_runs_on_every_call() {
+    let output = run(r#"f() { trap "echo ret" RETURN; echo in; }; f; f"#);
+    assert_eq!(output.stdout, "in\nret\nin\nret\n");
+}